@@ -0,0 +1,716 @@
+// ai.rs
+pub mod backend;
+pub mod knowledge;
+pub mod moderation;
+pub mod tools;
+
+use crate::database::AskUsage;
+use crate::error::Error;
+use crate::messages::{self, MAX_MESSAGE_LENGTH};
+use crate::Data;
+use tools::ToolScope;
+use async_openai::types::{
+    ChatCompletionMessageToolCall,
+    ChatCompletionMessageToolCallChunk,
+    ChatCompletionRequestAssistantMessageArgs,
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestMessageContentPartImageArgs,
+    ChatCompletionRequestMessageContentPartTextArgs,
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestToolMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    ChatCompletionRequestUserMessageContent,
+    ChatCompletionRequestUserMessageContentPart,
+    ChatCompletionResponseStream,
+    ChatCompletionStreamOptions,
+    ChatCompletionToolChoiceOption,
+    ChatCompletionToolType,
+    CompletionUsage,
+    CreateChatCompletionRequest,
+    CreateChatCompletionRequestArgs,
+    FunctionCall,
+    ImageDetail,
+    ImageUrlArgs,
+};
+use futures::StreamExt;
+use poise::{CreateReply, ReplyHandle};
+use poise::serenity_prelude::{
+    Attachment, ChannelId, Context, CreateAllowedMentions, CreateMessage, EditMessage, GuildId, Http, Message, MessageId, UserId,
+};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Rough number of tokens of earlier conversation sent along with each question
+const HISTORY_TOKEN_BUDGET: usize = 2000;
+/// Messages kept per channel. Anything older wouldn't fit in the budget anyway.
+const HISTORY_ROWS: i64 = 50;
+/// How often a streaming answer's message is edited with what's arrived so far
+const PREVIEW_INTERVAL: Duration = Duration::from_millis(1200);
+/// Most images sent along with one question
+pub const MAX_IMAGES: usize = 4;
+/// Largest image the model accepts, in bytes
+const MAX_IMAGE_SIZE: u32 = 20 * 1024 * 1024;
+/// Image formats the model accepts
+const IMAGE_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+/// Rough tokens an image costs, for servers that don't report usage
+const IMAGE_TOKEN_ESTIMATE: usize = 765;
+/// Asked when someone sends only images
+pub const DEFAULT_IMAGE_QUESTION: &str = "What does this show?";
+/// Rounds of tool calls allowed before the model has to answer with what it has
+const MAX_TOOL_ROUNDS: usize = 3;
+/// Most tool calls taken from one response. The model numbers them, so this bounds the list.
+const MAX_TOOL_CALLS: usize = 16;
+/// One-off tasks like summaries and translations should stick to the text they're given
+const TASK_TEMPERATURE: f32 = 0.3;
+/// Characters of flagged text quoted in the moderation log
+const INCIDENT_EXCERPT_LENGTH: usize = 300;
+
+/// Told to members whose question moderation flagged
+pub const QUESTION_BLOCKED: &str = "Your question was flagged by content moderation, so it wasn't sent.";
+/// Shown in place of an answer moderation flagged
+pub const ANSWER_WITHHELD: &str = "⚠️ The answer was withheld because it was flagged by content moderation.";
+
+/// The assistant settings in effect for a guild: its `/askconfig` overrides on top of the config
+pub struct AskOptions {
+    pub model: String,
+    pub vision_model: String,
+    pub system_prompt: String,
+    pub persona: Option<String>,
+    pub max_tokens: u32,
+    pub temperature: f32,
+}
+
+impl AskOptions {
+    pub async fn for_guild(data: &Data, guild_id: Option<GuildId>) -> Result<Self, Error> {
+        let config = &data.config.ask;
+        let settings = match guild_id {
+            Some(guild_id) => data.database.fetch_ask_settings(guild_id.get() as i64).await?,
+            None => Default::default(),
+        };
+
+        Ok(Self {
+            // A model taken off the allow-list since the guild picked it falls back to the default
+            model: settings.model
+                .filter(|model| config.model(model).is_some())
+                .unwrap_or_else(|| config.model.clone()),
+            vision_model: config.vision_model.clone(),
+            system_prompt: settings.system_prompt.unwrap_or_else(|| config.system_prompt.clone()),
+            persona: settings.persona.or_else(|| config.persona.clone()),
+            max_tokens: settings.max_tokens.map_or(config.max_tokens, |tokens| tokens as u32),
+            temperature: settings.temperature.unwrap_or(config.temperature),
+        })
+    }
+
+    fn instructions(&self) -> String {
+        match &self.persona {
+            Some(persona) => format!("{}\n\n{}", self.system_prompt, persona),
+            None => self.system_prompt.clone(),
+        }
+    }
+}
+
+/// An answer being streamed in from the model
+pub struct AnswerStream<'a> {
+    data: &'a Data,
+    inner: ChatCompletionResponseStream,
+    /// What was asked, kept to follow up with the results of any tool calls
+    request: CreateChatCompletionRequest,
+    /// Who the tools run for, if the model was offered any
+    tools: Option<ToolScope>,
+    /// Tool calls the model is asking for in the current round
+    tool_calls: Vec<PendingToolCall>,
+    tool_rounds: usize,
+    /// Our own guess at the prompt's size, for servers that don't report usage
+    prompt_estimate: usize,
+    /// Everything received so far
+    pub text: String,
+    usage: Option<CompletionUsage>,
+    last_preview: Option<Instant>,
+    /// Set once a preview fails moderation, after which no more are shown
+    preview_blocked: bool,
+    /// Knowledge base documents the model was given, in the order it cites them
+    sources: Vec<String>,
+}
+
+/// A tool call as it's streamed in, a few characters of arguments at a time
+#[derive(Default)]
+struct PendingToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+impl AnswerStream<'_> {
+    /// Waits for the next piece of the answer, returning `false` once it's complete. Any tools
+    /// the model calls along the way are run and the answer picks up with their results.
+    pub async fn next(&mut self) -> Result<bool, Error> {
+        loop {
+            let Some(chunk) = self.inner.next().await else {
+                if self.tool_calls.is_empty() {
+                    return Ok(false);
+                }
+                self.run_tools().await?;
+                continue;
+            };
+            let chunk = chunk?;
+
+            for choice in chunk.choices {
+                if let Some(content) = choice.delta.content {
+                    self.text += &content;
+                }
+                for call in choice.delta.tool_calls.into_iter().flatten() {
+                    self.add_tool_call(call);
+                }
+            }
+            if let Some(usage) = chunk.usage {
+                // Each round of tool calls is a request of its own, with its own usage
+                self.usage = Some(match self.usage.take() {
+                    Some(total) => CompletionUsage {
+                        prompt_tokens: total.prompt_tokens + usage.prompt_tokens,
+                        completion_tokens: total.completion_tokens + usage.completion_tokens,
+                        total_tokens: total.total_tokens + usage.total_tokens,
+                        ..usage
+                    },
+                    None => usage,
+                });
+            }
+            return Ok(true);
+        }
+    }
+
+    fn add_tool_call(&mut self, chunk: ChatCompletionMessageToolCallChunk) {
+        let index = chunk.index as usize;
+        if index >= MAX_TOOL_CALLS {
+            tracing::warn!("Ignoring tool call with index {} from the model", index);
+            return;
+        }
+        if self.tool_calls.len() <= index {
+            self.tool_calls.resize_with(index + 1, Default::default);
+        }
+        let call = &mut self.tool_calls[index];
+        if let Some(id) = chunk.id {
+            call.id = id;
+        }
+        if let Some(function) = chunk.function {
+            call.name += function.name.as_deref().unwrap_or_default();
+            call.arguments += function.arguments.as_deref().unwrap_or_default();
+        }
+    }
+
+    /// Runs the tools the model asked for and starts the next round with their results
+    async fn run_tools(&mut self) -> Result<(), Error> {
+        let calls = std::mem::take(&mut self.tool_calls);
+        let Some(scope) = &self.tools else {
+            return Err(Error::Unknown("The model called a tool it wasn't offered".to_string()));
+        };
+
+        let mut assistant = ChatCompletionRequestAssistantMessageArgs::default();
+        assistant.tool_calls(calls.iter().map(|call| ChatCompletionMessageToolCall {
+            id: call.id.clone(),
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionCall { name: call.name.clone(), arguments: call.arguments.clone() },
+        }).collect::<Vec<_>>());
+        if !self.text.is_empty() {
+            assistant.content(self.text.as_str());
+        }
+        self.request.messages.push(assistant.build()?.into());
+
+        for call in calls {
+            let result = tools::run(self.data, scope, &call.name, &call.arguments).await?;
+            self.prompt_estimate += estimate_tokens(&result);
+            self.request.messages.push(ChatCompletionRequestToolMessageArgs::default()
+                .tool_call_id(call.id)
+                .content(result)
+                .build()?
+                .into());
+        }
+
+        self.tool_rounds += 1;
+        if self.tool_rounds >= MAX_TOOL_ROUNDS {
+            self.request.tool_choice = Some(ChatCompletionToolChoiceOption::None);
+        }
+        self.inner = self.data.llm.stream_chat(self.request.clone()).await?;
+        Ok(())
+    }
+
+    /// The partial answer to show, if there is one and the last preview is old enough that
+    /// editing the message again won't run into Discord's rate limits. Blocked patterns are
+    /// redacted and the rest is moderated before it's shown. Once a preview is flagged no more
+    /// are given, and the finished answer's own check reports it and withholds it.
+    pub async fn preview_due(&mut self) -> Result<Option<String>, Error> {
+        if self.preview_blocked || self.text.trim().is_empty()
+            || self.last_preview.is_some_and(|at| at.elapsed() < PREVIEW_INTERVAL)
+        {
+            return Ok(None);
+        }
+        self.last_preview = Some(Instant::now());
+
+        let redacted = self.data.moderator.redact(&self.text);
+        if !self.data.moderator.check(&redacted).await?.is_empty() {
+            self.preview_blocked = true;
+            return Ok(None);
+        }
+
+        let preview = messages::split_message(&redacted, MAX_MESSAGE_LENGTH - 2).remove(0);
+        Ok(Some(format!("{} ▌", preview)))
+    }
+
+    /// Tokens the answer used, estimated if the server didn't say
+    pub fn usage(&self) -> AnswerUsage {
+        let (prompt_tokens, completion_tokens) = match &self.usage {
+            Some(usage) => (usage.prompt_tokens, usage.completion_tokens),
+            None => (self.prompt_estimate as u32, estimate_tokens(&self.text) as u32),
+        };
+        AnswerUsage { model: self.request.model.clone(), prompt_tokens, completion_tokens }
+    }
+
+    /// Lines shown under the finished answer: the knowledge base sources and the token usage
+    pub fn footer(&self) -> Option<String> {
+        let mut lines = Vec::new();
+        if !self.sources.is_empty() {
+            let sources: Vec<String> = self.sources.iter()
+                .enumerate()
+                .map(|(index, source)| format!("[{}] {}", index + 1, source))
+                .collect();
+            lines.push(format!("-# Sources: {}", sources.join(", ")));
+        }
+        if let Some(usage) = &self.usage {
+            lines.push(format!(
+                "-# {} tokens ({} prompt + {} completion)",
+                usage.total_tokens,
+                usage.prompt_tokens,
+                usage.completion_tokens,
+            ));
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+/// When each member last asked a question, for the cooldown between questions
+#[derive(Default)]
+pub struct AskCooldowns {
+    last_asked: Mutex<HashMap<UserId, Instant>>,
+}
+
+impl AskCooldowns {
+    /// Starts the member's cooldown, or returns how long is left of the current one
+    fn try_start(&self, user_id: UserId, cooldown: Duration) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut last_asked = self.last_asked.lock().unwrap();
+        last_asked.retain(|_, at| now.duration_since(*at) < cooldown);
+
+        if let Some(at) = last_asked.get(&user_id) {
+            return Err(cooldown - now.duration_since(*at));
+        }
+        last_asked.insert(user_id, now);
+        Ok(())
+    }
+}
+
+/// Checks a member's quota, their guild's quota and the cooldown, returning why they can't
+/// ask right now if they can't
+pub async fn refusal(data: &Data, guild_id: Option<GuildId>, user_id: UserId) -> Result<Option<String>, Error> {
+    let config = &data.config.ask;
+    let since = Utc::now() - chrono::Duration::days(1);
+
+    if config.user_daily_tokens > 0 {
+        let used = data.database.fetch_ask_usage(None, Some(user_id.get() as i64), since).await?;
+        if used.total_tokens() >= config.user_daily_tokens {
+            return Ok(Some(format!(
+                "You've used your {} tokens for the last 24 hours. Try again later.",
+                config.user_daily_tokens,
+            )));
+        }
+    }
+    if let (true, Some(guild_id)) = (config.guild_daily_tokens > 0, guild_id) {
+        let used = data.database.fetch_ask_usage(Some(guild_id.get() as i64), None, since).await?;
+        if used.total_tokens() >= config.guild_daily_tokens {
+            return Ok(Some("This server has used its AI allowance for the last 24 hours. Try again later.".to_string()));
+        }
+    }
+
+    if let Err(left) = data.ask_cooldowns.try_start(user_id, Duration::from_secs(config.cooldown_secs)) {
+        return Ok(Some(format!("Slow down! You can ask again in {} seconds.", left.as_secs() + 1)));
+    }
+
+    Ok(None)
+}
+
+/// Whether an attachment is an image the model can look at
+pub fn is_supported_image(attachment: &Attachment) -> bool {
+    attachment.size <= MAX_IMAGE_SIZE
+        && attachment.content_type.as_deref().is_some_and(|content_type| {
+            IMAGE_TYPES.iter().any(|supported| content_type.starts_with(supported))
+        })
+}
+
+/// Links to the images in a message, from its attachments and then its embeds
+pub fn image_urls(message: &Message) -> Vec<String> {
+    let attached = message.attachments.iter()
+        .filter(|attachment| is_supported_image(attachment))
+        .map(|attachment| attachment.url.clone());
+    let embedded = message.embeds.iter()
+        .flat_map(|embed| [embed.image.as_ref().map(|image| &image.url), embed.thumbnail.as_ref().map(|thumbnail| &thumbnail.url)])
+        .flatten()
+        .cloned();
+
+    attached.chain(embedded).take(MAX_IMAGES).collect()
+}
+
+/// Runs a question or answer past moderation, reporting it to the guild's moderation log if
+/// it's flagged. Returns whether it was.
+pub async fn screen(
+    http: &Http,
+    data: &Data,
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+    user_id: UserId,
+    kind: &str,
+    text: &str,
+) -> Result<bool, Error> {
+    let reasons = data.moderator.check(text).await?;
+    if reasons.is_empty() {
+        return Ok(false);
+    }
+
+    tracing::info!("Blocked an AI {} for user {} in channel {}: {}", kind, user_id, channel_id, reasons.join(", "));
+    if let Some(guild_id) = guild_id {
+        let excerpt: String = text.chars().take(INCIDENT_EXCERPT_LENGTH).collect();
+        let notice = format!(
+            "🛡️ Blocked an AI {} for <@{}> in <#{}> ({})\n>>> {}",
+            kind,
+            user_id,
+            channel_id,
+            reasons.join(", "),
+            excerpt,
+        );
+        messages::alert_mod_log(http, data, guild_id, &notice).await?;
+    }
+
+    Ok(true)
+}
+
+/// Tokens a finished answer used
+pub struct AnswerUsage {
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// Records the tokens a finished answer used
+pub async fn record_usage(data: &Data, guild_id: Option<GuildId>, user_id: UserId, usage: AnswerUsage) -> Result<(), Error> {
+    let AnswerUsage { model, prompt_tokens, completion_tokens } = usage;
+    let cost = data.config.ask.model(&model).map_or(0.0, |prices| {
+        (prompt_tokens as f64 * prices.prompt_price_per_million
+            + completion_tokens as f64 * prices.completion_price_per_million) / 1_000_000.0
+    });
+
+    data.database.record_ask_usage(&AskUsage {
+        guild_id: guild_id.map(|id| id.get() as i64),
+        user_id: user_id.get() as i64,
+        model: &model,
+        prompt_tokens: prompt_tokens as i32,
+        completion_tokens: completion_tokens as i32,
+        cost,
+    }).await
+}
+
+/// Streams a command's answer into a reply, previewing it as it's written, then records its usage
+/// and moderates it as `kind` before the final version goes out with `footer` underneath.
+/// Returns the last message sent, or `None` if there was no answer or it was withheld.
+pub async fn stream_into_reply<'a>(
+    ctx: poise::Context<'_, Data, Error>,
+    stream: &mut AnswerStream<'a>,
+    kind: &str,
+    footer: impl FnOnce(&AnswerStream<'a>) -> Option<String>,
+) -> Result<Option<MessageId>, Error> {
+    let mut preview: Option<ReplyHandle> = None;
+    while stream.next().await? {
+        if let Some(partial) = stream.preview_due().await? {
+            let reply = CreateReply::default()
+                .content(partial)
+                .allowed_mentions(CreateAllowedMentions::new());
+            match &preview {
+                Some(preview) => preview.edit(ctx, reply).await?,
+                None => preview = Some(ctx.send(reply).await?),
+            }
+        }
+    }
+    record_usage(ctx.data(), ctx.guild_id(), ctx.author().id, stream.usage()).await?;
+
+    if stream.text.trim().is_empty() {
+        ctx.say("No response received.").await?;
+        return Ok(None);
+    }
+
+    if screen(ctx.http(), ctx.data(), ctx.guild_id(), ctx.channel_id(), ctx.author().id, kind, &stream.text).await? {
+        messages::finish_reply(ctx, preview, ANSWER_WITHHELD, None).await?;
+        return Ok(None);
+    }
+
+    let footer = footer(stream);
+    Ok(Some(messages::finish_reply(ctx, preview, &stream.text, footer.as_deref()).await?))
+}
+
+/// Starts answering `user_id`'s question about any images linked in `images`, with the
+/// channel's earlier conversation as context and, if enabled, tools to look up the bot's data
+pub async fn stream_answer<'a>(
+    data: &'a Data,
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+    user_id: UserId,
+    question: &str,
+    images: &[String],
+) -> Result<AnswerStream<'a>, Error> {
+    let options = AskOptions::for_guild(data, guild_id).await?;
+    let history = data.database.fetch_ask_history(channel_id.get() as i64, HISTORY_ROWS).await?;
+
+    // Walk back from the newest message until the budget runs out
+    let mut budget = HISTORY_TOKEN_BUDGET.saturating_sub(estimate_tokens(question));
+    let mut start = history.len();
+    for message in history.iter().rev() {
+        let tokens = estimate_tokens(&message.content);
+        if tokens > budget {
+            break;
+        }
+        budget -= tokens;
+        start -= 1;
+    }
+    // Don't open the context with an answer whose question was cut off
+    if history.get(start).is_some_and(|message| message.role == "assistant") {
+        start += 1;
+    }
+
+    let history = &history[start.min(history.len())..];
+    let instructions = options.instructions();
+    let excerpts = match guild_id {
+        Some(guild_id) => knowledge::search(data, guild_id, user_id, question).await?,
+        None => Default::default(),
+    };
+    let excerpt_prompt = (!excerpts.is_empty()).then(|| excerpts.prompt());
+    let prompt_estimate = estimate_tokens(&instructions)
+        + excerpt_prompt.as_deref().map_or(0, estimate_tokens)
+        + history.iter().map(|message| estimate_tokens(&message.content)).sum::<usize>()
+        + estimate_tokens(question)
+        + images.len() * IMAGE_TOKEN_ESTIMATE;
+
+    let mut messages: Vec<ChatCompletionRequestMessage> = vec![
+        ChatCompletionRequestSystemMessageArgs::default()
+            .content(instructions)
+            .build()?
+            .into(),
+    ];
+    if let Some(excerpt_prompt) = excerpt_prompt {
+        messages.push(ChatCompletionRequestSystemMessageArgs::default()
+            .content(excerpt_prompt)
+            .build()?
+            .into());
+    }
+    for message in history {
+        messages.push(if message.role == "assistant" {
+            ChatCompletionRequestAssistantMessageArgs::default()
+                .content(message.content.as_str())
+                .build()?
+                .into()
+        } else {
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(message.content.as_str())
+                .build()?
+                .into()
+        });
+    }
+    // Plain text unless there are images, as not every compatible server understands parts
+    let content: ChatCompletionRequestUserMessageContent = if images.is_empty() {
+        question.into()
+    } else {
+        let mut parts: Vec<ChatCompletionRequestUserMessageContentPart> = vec![
+            ChatCompletionRequestMessageContentPartTextArgs::default().text(question).build()?.into(),
+        ];
+        for url in images {
+            parts.push(ChatCompletionRequestMessageContentPartImageArgs::default()
+                .image_url(ImageUrlArgs::default().url(url).detail(ImageDetail::Auto).build()?)
+                .build()?
+                .into());
+        }
+        parts.into()
+    };
+    messages.push(
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(content)
+            .build()?
+            .into(),
+    );
+
+    let model = if images.is_empty() { options.model } else { options.vision_model };
+    let mut request = CreateChatCompletionRequestArgs::default();
+    request
+        .max_tokens(options.max_tokens)
+        .temperature(options.temperature)
+        .model(&model)
+        .messages(messages)
+        .stream_options(ChatCompletionStreamOptions { include_usage: true });
+
+    let tools = data.config.ask.tools.then_some(ToolScope { guild_id, user_id });
+    if tools.is_some() {
+        request.tools(tools::definitions()?);
+    }
+
+    let mut stream = open_stream(data, request.build()?, tools, prompt_estimate).await?;
+    stream.sources = excerpts.sources;
+    Ok(stream)
+}
+
+/// Starts a one-off task like a summary or translation of `input`, with the guild's model and
+/// limits but `instructions` in place of its prompts and no conversation history
+pub async fn stream_task<'a>(
+    data: &'a Data,
+    guild_id: Option<GuildId>,
+    instructions: &str,
+    input: &str,
+) -> Result<AnswerStream<'a>, Error> {
+    let options = AskOptions::for_guild(data, guild_id).await?;
+    let prompt_estimate = estimate_tokens(instructions) + estimate_tokens(input);
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .max_tokens(options.max_tokens)
+        .temperature(TASK_TEMPERATURE)
+        .model(&options.model)
+        .messages([
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(instructions)
+                .build()?
+                .into(),
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(input)
+                .build()?
+                .into(),
+        ])
+        .stream_options(ChatCompletionStreamOptions { include_usage: true })
+        .build()?;
+
+    open_stream(data, request, None, prompt_estimate).await
+}
+
+async fn open_stream(
+    data: &Data,
+    request: CreateChatCompletionRequest,
+    tools: Option<ToolScope>,
+    prompt_estimate: usize,
+) -> Result<AnswerStream<'_>, Error> {
+    Ok(AnswerStream {
+        data,
+        inner: data.llm.stream_chat(request.clone()).await?,
+        request,
+        tools,
+        tool_calls: Vec::new(),
+        tool_rounds: 0,
+        prompt_estimate,
+        text: String::new(),
+        usage: None,
+        last_preview: None,
+        preview_blocked: false,
+        sources: Vec::new(),
+    })
+}
+
+/// Adds a question and the message the bot answered it with to the channel's conversation.
+/// Images aren't kept, only a note of how many there were.
+pub async fn remember(
+    data: &Data,
+    channel_id: ChannelId,
+    question: &str,
+    image_count: usize,
+    answer: &str,
+    answer_message_id: MessageId,
+) -> Result<(), Error> {
+    let question = match image_count {
+        0 => question.to_string(),
+        count => format!("{}\n[{} image(s) attached]", question, count),
+    };
+    data.database
+        .store_ask_exchange(
+            channel_id.get() as i64,
+            &question,
+            answer,
+            answer_message_id.get() as i64,
+            HISTORY_ROWS,
+        )
+        .await
+}
+
+/// Continues the conversation when someone replies to one of the bot's `/ask` answers
+pub async fn handle_reply(ctx: &Context, message: &Message, data: &Data) -> Result<(), Error> {
+    let Some(referenced) = &message.referenced_message else {
+        return Ok(());
+    };
+    let images = image_urls(message);
+    if message.author.bot || (message.content.trim().is_empty() && images.is_empty())
+        || !data.database.is_ask_answer(referenced.id.get() as i64).await?
+    {
+        return Ok(());
+    }
+    let question = match message.content.trim() {
+        "" => DEFAULT_IMAGE_QUESTION,
+        content => content,
+    };
+
+    if let Some(refusal) = refusal(data, message.guild_id, message.author.id).await? {
+        message.reply(ctx, refusal).await?;
+        return Ok(());
+    }
+    if screen(&ctx.http, data, message.guild_id, message.channel_id, message.author.id, "question", question).await? {
+        message.reply(ctx, QUESTION_BLOCKED).await?;
+        return Ok(());
+    }
+
+    let typing = message.channel_id.start_typing(&ctx.http);
+    let mut stream = stream_answer(data, message.guild_id, message.channel_id, message.author.id, question, &images).await?;
+
+    let mut preview: Option<Message> = None;
+    while stream.next().await? {
+        if let Some(partial) = stream.preview_due().await? {
+            match &mut preview {
+                Some(preview) => preview.edit(ctx, EditMessage::new().content(partial)).await?,
+                None => {
+                    preview = Some(message.channel_id.send_message(ctx, CreateMessage::new()
+                        .content(partial)
+                        .reference_message(message)
+                        .allowed_mentions(CreateAllowedMentions::new()))
+                        .await?);
+                }
+            }
+        }
+    }
+    typing.stop();
+    record_usage(data, message.guild_id, message.author.id, stream.usage()).await?;
+
+    if stream.text.trim().is_empty() {
+        message.reply(ctx, "No response received.").await?;
+        return Ok(());
+    }
+
+    if screen(&ctx.http, data, message.guild_id, message.channel_id, message.author.id, "answer", &stream.text).await? {
+        messages::finish_message(&ctx.http, message.channel_id, preview, Some(message), ANSWER_WITHHELD, None).await?;
+        return Ok(());
+    }
+
+    let footer = stream.footer();
+    let reply_id = messages::finish_message(
+        &ctx.http,
+        message.channel_id,
+        preview,
+        Some(message),
+        &stream.text,
+        footer.as_deref(),
+    ).await?;
+    remember(data, message.channel_id, question, images.len(), &stream.text, reply_id).await?;
+
+    Ok(())
+}
+
+/// Very rough token count: about four characters per token, plus a little per message
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count() / 4 + 4
+}
\ No newline at end of file
@@ -0,0 +1,65 @@
+// ai/backend.rs
+use crate::config::{Config, LlmProvider};
+use crate::error::Error;
+use async_openai::config::OpenAIConfig;
+use async_openai::types::{
+    ChatCompletionResponseStream, CreateChatCompletionRequest, CreateEmbeddingRequest, CreateEmbeddingResponse,
+};
+use async_openai::Client as OpenAIClient;
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+/// A chat completion API the AI features can be pointed at
+pub trait LlmBackend: Send + Sync {
+    /// Starts a chat completion whose answer is streamed back piece by piece
+    fn stream_chat(&self, request: CreateChatCompletionRequest) -> BoxFuture<'_, Result<ChatCompletionResponseStream, Error>>;
+
+    /// Turns text into embedding vectors, for finding related text
+    fn embed(&self, request: CreateEmbeddingRequest) -> BoxFuture<'_, Result<CreateEmbeddingResponse, Error>>;
+}
+
+/// Builds the backend selected in the config
+pub fn from_config(config: &Config) -> Arc<dyn LlmBackend> {
+    let mut openai_config = OpenAIConfig::new().with_api_key(&config.openai_api_key);
+    if let Some(api_base) = &config.llm.api_base {
+        openai_config = openai_config.with_api_base(api_base.trim_end_matches('/'));
+    }
+    let client = OpenAIClient::with_config(openai_config);
+
+    match config.llm.provider {
+        LlmProvider::OpenAi => Arc::new(OpenAi { client }),
+        LlmProvider::OpenAiCompatible => Arc::new(OpenAiCompatible { client }),
+    }
+}
+
+/// The OpenAI API
+pub struct OpenAi {
+    client: OpenAIClient<OpenAIConfig>,
+}
+
+impl LlmBackend for OpenAi {
+    fn stream_chat(&self, request: CreateChatCompletionRequest) -> BoxFuture<'_, Result<ChatCompletionResponseStream, Error>> {
+        Box::pin(async move { Ok(self.client.chat().create_stream(request).await?) })
+    }
+
+    fn embed(&self, request: CreateEmbeddingRequest) -> BoxFuture<'_, Result<CreateEmbeddingResponse, Error>> {
+        Box::pin(async move { Ok(self.client.embeddings().create(request).await?) })
+    }
+}
+
+/// A server that speaks the OpenAI API, such as llama.cpp, Ollama or vLLM. These often lag
+/// behind OpenAI, so requests are kept to the fields they all understand.
+pub struct OpenAiCompatible {
+    client: OpenAIClient<OpenAIConfig>,
+}
+
+impl LlmBackend for OpenAiCompatible {
+    fn stream_chat(&self, mut request: CreateChatCompletionRequest) -> BoxFuture<'_, Result<ChatCompletionResponseStream, Error>> {
+        request.stream_options = None;
+        Box::pin(async move { Ok(self.client.chat().create_stream(request).await?) })
+    }
+
+    fn embed(&self, request: CreateEmbeddingRequest) -> BoxFuture<'_, Result<CreateEmbeddingResponse, Error>> {
+        Box::pin(async move { Ok(self.client.embeddings().create(request).await?) })
+    }
+}
\ No newline at end of file
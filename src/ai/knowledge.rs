@@ -0,0 +1,100 @@
+// ai/knowledge.rs
+use crate::database::{AskUsage, KbChunk};
+use crate::error::Error;
+use crate::messages;
+use crate::Data;
+use async_openai::types::CreateEmbeddingRequestArgs;
+use poise::serenity_prelude::{GuildId, UserId};
+
+/// Pieces embedded per request
+const EMBEDDING_BATCH: usize = 64;
+
+/// Excerpts from a guild's knowledge base found for a question
+#[derive(Default)]
+pub struct Excerpts {
+    /// The documents the excerpts are from, numbered from 1 in this order for citations
+    pub sources: Vec<String>,
+    chunks: Vec<KbChunk>,
+}
+
+impl Excerpts {
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Instructions giving the model the excerpts and how to cite them
+    pub fn prompt(&self) -> String {
+        let mut prompt = "Excerpts from this server's guides follow. Prefer them over what you know \
+            when they answer the question, cite the ones you use by number like [1], and don't cite \
+            anything else."
+            .to_string();
+        for chunk in &self.chunks {
+            let number = self.sources.iter().position(|source| *source == chunk.document).unwrap_or(0) + 1;
+            prompt += &format!("\n\n[{}] From \"{}\":\n{}", number, chunk.document, chunk.content);
+        }
+        prompt
+    }
+}
+
+/// Splits a document `user_id` is adding into pieces and embeds them, ready for
+/// `Database::store_kb_document`
+pub async fn embed_document(
+    data: &Data,
+    guild_id: GuildId,
+    user_id: UserId,
+    text: &str,
+) -> Result<Vec<(String, Vec<f32>)>, Error> {
+    let chunks = messages::split_message(text, data.config.knowledge_base.chunk_size);
+
+    let mut embedded = Vec::with_capacity(chunks.len());
+    for batch in chunks.chunks(EMBEDDING_BATCH) {
+        let vectors = embed(data, guild_id, user_id, batch.to_vec()).await?;
+        embedded.extend(batch.iter().cloned().zip(vectors));
+    }
+    Ok(embedded)
+}
+
+/// The pieces of a guild's knowledge base most related to `user_id`'s question, best first
+pub async fn search(data: &Data, guild_id: GuildId, user_id: UserId, question: &str) -> Result<Excerpts, Error> {
+    let config = &data.config.knowledge_base;
+    if !data.database.has_kb_chunks(guild_id.get() as i64).await? {
+        return Ok(Excerpts::default());
+    }
+
+    let query = embed(data, guild_id, user_id, vec![question.to_string()]).await?.pop().unwrap_or_default();
+    let chunks = data.database
+        .search_kb_chunks(guild_id.get() as i64, &query, config.min_similarity, config.results as i64)
+        .await?;
+
+    let mut sources: Vec<String> = Vec::new();
+    for chunk in &chunks {
+        if !sources.contains(&chunk.document) {
+            sources.push(chunk.document.clone());
+        }
+    }
+
+    Ok(Excerpts { sources, chunks })
+}
+
+/// Embeds `inputs`, recording the tokens against `user_id` and the guild like an answer's
+async fn embed(data: &Data, guild_id: GuildId, user_id: UserId, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, Error> {
+    let config = &data.config.knowledge_base;
+    let request = CreateEmbeddingRequestArgs::default()
+        .model(&config.embedding_model)
+        .input(inputs)
+        .build()?;
+    let mut response = data.llm.embed(request).await?;
+
+    let tokens = response.usage.prompt_tokens;
+    data.database.record_ask_usage(&AskUsage {
+        guild_id: Some(guild_id.get() as i64),
+        user_id: user_id.get() as i64,
+        model: &config.embedding_model,
+        prompt_tokens: tokens as i32,
+        completion_tokens: 0,
+        cost: tokens as f64 * config.price_per_million / 1_000_000.0,
+    }).await?;
+
+    response.data.sort_by_key(|embedding| embedding.index);
+    Ok(response.data.into_iter().map(|embedding| embedding.embedding).collect())
+}
\ No newline at end of file
@@ -0,0 +1,69 @@
+// ai/moderation.rs
+use crate::config::Config;
+use crate::error::Error;
+use async_openai::config::OpenAIConfig;
+use async_openai::types::CreateModerationRequestArgs;
+use async_openai::Client as OpenAIClient;
+use regex::Regex;
+
+/// What blocked text is replaced with when it's redacted
+const REDACTED: &str = "█████";
+
+/// Screens what goes into and comes out of the AI assistant
+pub struct Moderator {
+    /// Set when the OpenAI moderation endpoint is in use
+    openai: Option<OpenAIClient<OpenAIConfig>>,
+    patterns: Vec<Regex>,
+}
+
+impl Moderator {
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let has_key = !config.openai_api_key.trim().is_empty();
+        let openai = match config.moderation.use_openai {
+            Some(true) if !has_key => {
+                return Err(Error::Unknown("[moderation] use_openai needs openai_api_key to be set".to_string()));
+            }
+            Some(use_openai) => use_openai,
+            None => has_key,
+        };
+        let openai = openai.then(|| {
+            OpenAIClient::with_config(OpenAIConfig::new().with_api_key(&config.openai_api_key))
+        });
+        let patterns = config.moderation.blocked_patterns.iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { openai, patterns })
+    }
+
+    /// Returns the reasons the text should be blocked, or an empty list if it's fine
+    pub async fn check(&self, text: &str) -> Result<Vec<String>, Error> {
+        let mut reasons: Vec<String> = self.patterns.iter()
+            .filter(|pattern| pattern.is_match(text))
+            .map(|pattern| format!("matched `{}`", pattern.as_str()))
+            .collect();
+
+        if let Some(client) = &self.openai {
+            let request = CreateModerationRequestArgs::default().input(text).build()?;
+            let response = client.moderations().create(request).await?;
+
+            for result in response.results.into_iter().filter(|result| result.flagged) {
+                // The category list grows over time, so read it generically rather than field by field
+                if let Ok(serde_json::Value::Object(categories)) = serde_json::to_value(&result.categories) {
+                    reasons.extend(categories.into_iter()
+                        .filter(|(_, flagged)| flagged.as_bool() == Some(true))
+                        .map(|(category, _)| category));
+                }
+            }
+        }
+
+        Ok(reasons)
+    }
+
+    /// Blanks out anything matching the blocked patterns
+    pub fn redact(&self, text: &str) -> String {
+        self.patterns.iter().fold(text.to_string(), |text, pattern| {
+            pattern.replace_all(&text, REDACTED).into_owned()
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,117 @@
+// ai/tools.rs
+use crate::error::Error;
+use crate::Data;
+use async_openai::types::{ChatCompletionTool, ChatCompletionToolArgs, FunctionObjectArgs};
+use chrono::Utc;
+use poise::serenity_prelude::{GuildId, UserId};
+use serde_json::{json, Value};
+
+/// Upcoming raids listed by `next_raids`
+const MAX_RAIDS: usize = 5;
+
+/// Who a question was asked by and where, which is all the tools get to see
+pub struct ToolScope {
+    pub guild_id: Option<GuildId>,
+    pub user_id: UserId,
+}
+
+/// The tools offered to the model. All of them only read.
+pub fn definitions() -> Result<Vec<ChatCompletionTool>, Error> {
+    Ok(vec![
+        tool(
+            "next_raids",
+            "Lists this server's upcoming scheduled raids, soonest first. To give a raid's time, put its \
+             discord_timestamp in the answer as it is, so each reader sees it in their own timezone.",
+            json!({ "type": "object", "properties": {} }),
+        )?,
+        tool(
+            "hug_count",
+            "Gets how many hugs a member has received with /animehug",
+            json!({
+                "type": "object",
+                "properties": {
+                    "user_id": {
+                        "type": "string",
+                        "description": "The member's Discord ID, e.g. 123 from a mention like <@123>. Leave out for the person asking.",
+                    },
+                },
+            }),
+        )?,
+        tool(
+            "server_info",
+            "Gets this server's name, owner and member count",
+            json!({ "type": "object", "properties": {} }),
+        )?,
+    ])
+}
+
+fn tool(name: &str, description: &str, parameters: Value) -> Result<ChatCompletionTool, Error> {
+    Ok(ChatCompletionToolArgs::default()
+        .function(FunctionObjectArgs::default()
+            .name(name)
+            .description(description)
+            .parameters(parameters)
+            .build()?)
+        .build()?)
+}
+
+/// Runs a tool the model asked for and returns its result as JSON. Mistakes the model can
+/// correct, like a bad argument, go back to it in the result rather than failing the answer.
+pub async fn run(data: &Data, scope: &ToolScope, name: &str, arguments: &str) -> Result<String, Error> {
+    let arguments: Value = serde_json::from_str(arguments).unwrap_or(Value::Null);
+
+    let result = match name {
+        "next_raids" => next_raids(data, scope).await?,
+        "hug_count" => hug_count(data, scope, &arguments).await?,
+        "server_info" => server_info(data, scope).await?,
+        _ => json!({ "error": format!("There's no tool called {}", name) }),
+    };
+    tracing::debug!("AI tool {} for user {} returned {}", name, scope.user_id, result);
+
+    Ok(result.to_string())
+}
+
+async fn next_raids(data: &Data, scope: &ToolScope) -> Result<Value, Error> {
+    let Some(guild_id) = scope.guild_id else {
+        return Ok(json!({ "error": "Raids are only scheduled in servers" }));
+    };
+    let raids = data.database.fetch_upcoming_raid_events(guild_id.get() as i64).await?;
+
+    Ok(json!({
+        "now": Utc::now().to_rfc3339(),
+        "raids": raids.iter().take(MAX_RAIDS).map(|raid| json!({
+            "raid": raid.raid,
+            "start_time": raid.start_time.to_rfc3339(),
+            "discord_timestamp": format!("<t:{}:F>", raid.start_time.timestamp()),
+            "channel": format!("<#{}>", raid.channel_id),
+        })).collect::<Vec<_>>(),
+    }))
+}
+
+async fn hug_count(data: &Data, scope: &ToolScope, arguments: &Value) -> Result<Value, Error> {
+    let user_id = match arguments.get("user_id").and_then(Value::as_str) {
+        Some(user_id) => match user_id.trim_matches(|c: char| !c.is_ascii_digit()).parse::<u64>() {
+            Ok(user_id) if user_id > 0 => UserId::new(user_id),
+            _ => return Ok(json!({ "error": format!("\"{}\" isn't a Discord user ID", user_id) })),
+        },
+        None => scope.user_id,
+    };
+    let hugs = data.database.get_hug_count(user_id.get() as i64).await?;
+
+    Ok(json!({ "user": format!("<@{}>", user_id), "hugs": hugs }))
+}
+
+async fn server_info(data: &Data, scope: &ToolScope) -> Result<Value, Error> {
+    let Some(guild_id) = scope.guild_id else {
+        return Ok(json!({ "error": "This conversation isn't in a server" }));
+    };
+
+    Ok(match data.database.fetch_guild_info(guild_id.get() as i64).await? {
+        Some(info) => json!({
+            "name": info.guild_name,
+            "owner": format!("<@{}>", info.owner_id),
+            "member_count": info.member_count,
+        }),
+        None => json!({ "error": "Nothing is known about this server yet" }),
+    })
+}
\ No newline at end of file
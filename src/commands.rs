@@ -1,32 +1,69 @@
 // commands.rs
 mod warn;
 mod set_warn_channel;
-mod random_cat_image;
-mod random_capy_image;
+mod random_image;
 mod user_info;
 mod anime_hug;
-mod update_raid_time;
+pub mod update_raid_time;
 mod ask;
+mod ask_config;
 mod purge;
+mod lockdown;
+mod raid;
+mod raid_catalog;
+mod timezone;
+mod summarize;
+mod message_actions;
+mod kb;
 
+use crate::config::Config;
 use crate::error::Error;
 use crate::Data;
 use poise::serenity_prelude::Permissions;
 
-pub fn get_commands() -> Vec<poise::Command<Data, Error>> {
-    vec![
+pub fn get_commands(config: &Config) -> Vec<poise::Command<Data, Error>> {
+    let mut commands = vec![
         warn::warn(),
         set_warn_channel::setwarnchannel(),
-        random_cat_image::randomcatimage(),
-        random_capy_image::randomcapyimage(),
+        random_image::randomimage(),
         user_info::userinfo(),
         anime_hug::animehug(),
         update_raid_time::updateraidtime(),
+        raid::raid(),
+        timezone::timezone(),
         ask::ask(),
+        ask::askai(),
+        ask::askusage(),
+        summarize::summarize(),
+        message_actions::translate(),
+        message_actions::explain(),
+        kb::kb(),
         {
             let mut cmd = purge::purge();
             cmd.default_member_permissions = Permissions::MANAGE_MESSAGES;
             cmd
         },
-    ]
+        {
+            let mut cmd = ask::askreset();
+            cmd.default_member_permissions = Permissions::MANAGE_MESSAGES;
+            cmd
+        },
+        {
+            let mut cmd = ask_config::askconfig();
+            cmd.default_member_permissions = Permissions::MANAGE_GUILD;
+            cmd
+        },
+        {
+            let mut cmd = raid_catalog::raidcatalog();
+            cmd.default_member_permissions = Permissions::MANAGE_GUILD;
+            cmd
+        },
+        {
+            let mut cmd = lockdown::lockdown();
+            cmd.default_member_permissions = Permissions::MANAGE_GUILD;
+            cmd
+        },
+    ];
+    commands.extend(random_image::shortcuts(config));
+    commands
 }
\ No newline at end of file
@@ -13,20 +13,11 @@ pub async fn animehug(
 ) -> Result<(), Error> {
     ctx.defer().await?;
 
-    let client = reqwest::Client::new();
-    let response = client.get("https://nekos.best/api/v2/hug")
-        .send()
-        .await?
-        .json::<serde_json::Value>()
-        .await?;
-
-    let result = &response["results"][0];
-    let anime_name = result["anime_name"].as_str().unwrap_or("Unknown");
-    let image_url = result["url"].as_str().unwrap_or("");
+    let image = ctx.data().images.hug().await?;
 
     let embed = CreateEmbed::default()
-        .title(format!("From the Anime: {}", anime_name))
-        .image(image_url)
+        .title(format!("From the Anime: {}", image.title.as_deref().unwrap_or("Unknown")))
+        .image(image.url)
         .color(0x5865F2);
 
     let hug_count = ctx.data().database.increment_hug_count(user.id.get() as i64).await?;
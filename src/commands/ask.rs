@@ -1,15 +1,11 @@
 // commands/ask.rs
+use crate::ai;
+use crate::database::AskUsageSummary;
 use crate::error::Error;
 use crate::Data;
-use async_openai::{
-    types::{
-        ChatCompletionRequestSystemMessageArgs,
-        ChatCompletionRequestUserMessageArgs,
-        CreateChatCompletionRequestArgs,
-    },
-    Client as OpenAIClient,
-};
-use async_openai::config::OpenAIConfig;
+use chrono::{Duration, Utc};
+use poise::serenity_prelude::{Attachment, Message};
+use poise::CreateReply;
 
 type Context<'a> = poise::Context<'a, Data, Error>;
 
@@ -18,38 +14,133 @@ type Context<'a> = poise::Context<'a, Data, Error>;
 pub async fn ask(
     ctx: Context<'_>,
     #[description = "Your question"] question: String,
+    #[description = "An image to ask about, e.g. a screenshot"] image: Option<Attachment>,
 ) -> Result<(), Error> {
+    if image.as_ref().is_some_and(|image| !ai::is_supported_image(image)) {
+        return Err(Error::Unknown("The image must be a PNG, JPEG, GIF or WebP file of at most 20 MB".to_string()));
+    }
+    let images: Vec<String> = image.into_iter().map(|image| image.url).collect();
+
+    answer(ctx, &question, &images).await
+}
+
+#[derive(poise::Modal)]
+#[name = "Ask AI"]
+struct AskModal {
+    #[name = "Question"]
+    #[placeholder = "Leave empty to have the message explained"]
+    #[paragraph]
+    #[max_length = 1000]
+    question: Option<String>,
+}
+
+/// Ask the AI assistant about a message and its images
+#[poise::command(context_menu_command = "Ask AI")]
+pub async fn askai(
+    ctx: poise::ApplicationContext<'_, Data, Error>,
+    message: Message,
+) -> Result<(), Error> {
+    let images = ai::image_urls(&message);
+    if message.content.trim().is_empty() && images.is_empty() {
+        return Err(Error::Unknown("That message has no text or images to ask about".to_string()));
+    }
+
+    let Some(modal) = poise::execute_modal(ctx, None::<AskModal>, None).await? else {
+        return Ok(());
+    };
+    let question = modal.question.filter(|question| !question.trim().is_empty());
+
+    let question = match (question, message.content.trim()) {
+        (Some(question), "") => question,
+        (Some(question), content) => format!("{}\n\nMessage from {}:\n{}", question, message.author.name, content),
+        (None, "") => ai::DEFAULT_IMAGE_QUESTION.to_string(),
+        (None, content) => format!("Explain this message from {}:\n{}", message.author.name, content),
+    };
+
+    answer(ctx.into(), &question, &images).await
+}
+
+/// Answers a question from a command, streaming the answer into the reply
+async fn answer(ctx: Context<'_>, question: &str, images: &[String]) -> Result<(), Error> {
+    // Moderation can take longer than Discord waits for a first response
     ctx.defer().await?;
 
-    let config = OpenAIConfig::new().with_api_key(&ctx.data().config.openai_api_key);
-    let client = OpenAIClient::with_config(config);
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .max_tokens(512u32)
-        .model("gpt-3.5-turbo")
-        .messages([
-            ChatCompletionRequestSystemMessageArgs::default()
-                .content("You are a helpful assistant.")
-                .build()?
-                .into(),
-            ChatCompletionRequestUserMessageArgs::default()
-                .content(question)
-                .build()?
-                .into(),
-        ])
-        .build()?;
-
-    let response = client.chat().create(request).await?;
-
-    if let Some(choice) = response.choices.first() {
-        if let Some(content) = &choice.message.content {
-            ctx.say(content).await?;
-        } else {
-            ctx.say("No response content received.").await?;
-        }
-    } else {
-        ctx.say("No response received.").await?;
+    if let Some(refusal) = ai::refusal(ctx.data(), ctx.guild_id(), ctx.author().id).await? {
+        ctx.say(refusal).await?;
+        return Ok(());
+    }
+    if ai::screen(ctx.http(), ctx.data(), ctx.guild_id(), ctx.channel_id(), ctx.author().id, "question", question).await? {
+        ctx.say(ai::QUESTION_BLOCKED).await?;
+        return Ok(());
+    }
+
+    let mut stream = ai::stream_answer(ctx.data(), ctx.guild_id(), ctx.channel_id(), ctx.author().id, question, images).await?;
+    if let Some(message_id) = ai::stream_into_reply(ctx, &mut stream, "answer", ai::AnswerStream::footer).await? {
+        ai::remember(ctx.data(), ctx.channel_id(), question, images.len(), &stream.text, message_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Make the AI assistant forget the conversation in this channel
+///
+/// A command of its own rather than `/ask reset`, because Discord doesn't let a command with
+/// subcommands take options itself, and `/ask` takes the question.
+#[poise::command(slash_command)]
+pub async fn askreset(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.data().database.clear_ask_history(ctx.channel_id().get() as i64).await?;
+    ctx.say("🧹 Conversation cleared. The next question starts fresh.").await?;
+
+    Ok(())
+}
+
+/// Show how much the AI assistant has been used
+#[poise::command(slash_command)]
+pub async fn askusage(
+    ctx: Context<'_>,
+    #[description = "Number of days to look back (default 30)"] days: Option<i64>,
+) -> Result<(), Error> {
+    let days = days.unwrap_or(30).clamp(1, 3650);
+    let since = Utc::now() - Duration::days(days);
+    let database = &ctx.data().database;
+    let config = &ctx.data().config.ask;
+
+    let user_id = ctx.author().id.get() as i64;
+    let mine = database.fetch_ask_usage(None, Some(user_id), since).await?;
+    let mut lines = vec![
+        format!("**AI usage over the last {} day(s)**", days),
+        format!("You: {}", describe_usage(&mine)),
+    ];
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let guild = database.fetch_ask_usage(Some(guild_id.get() as i64), None, since).await?;
+        lines.push(format!("This server: {}", describe_usage(&guild)));
     }
 
+    if config.user_daily_tokens > 0 {
+        let today = database.fetch_ask_usage(None, Some(user_id), Utc::now() - Duration::days(1)).await?;
+        lines.push(format!(
+            "Your allowance: {} of {} tokens used in the last 24 hours",
+            today.total_tokens(),
+            config.user_daily_tokens,
+        ));
+    }
+
+    ctx.send(CreateReply::default()
+        .content(lines.join("\n"))
+        .ephemeral(true))
+        .await?;
+
     Ok(())
+}
+
+fn describe_usage(usage: &AskUsageSummary) -> String {
+    format!(
+        "{} question(s), {} tokens ({} prompt + {} completion), about ${:.4}",
+        usage.questions,
+        usage.total_tokens(),
+        usage.prompt_tokens,
+        usage.completion_tokens,
+        usage.cost,
+    )
 }
\ No newline at end of file
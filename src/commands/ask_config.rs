@@ -0,0 +1,143 @@
+// commands/ask_config.rs
+use crate::ai::AskOptions;
+use crate::error::Error;
+use crate::Data;
+use poise::serenity_prelude::CreateAllowedMentions;
+use poise::CreateReply;
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Longest system or persona prompt a guild can set
+const MAX_PROMPT_LENGTH: usize = 2000;
+
+#[derive(poise::ChoiceParameter)]
+pub enum AskSetting {
+    #[name = "Model"] Model,
+    #[name = "System prompt"] SystemPrompt,
+    #[name = "Persona"] Persona,
+    #[name = "Max tokens"] MaxTokens,
+    #[name = "Temperature"] Temperature,
+}
+
+/// Configure the AI assistant for this server
+#[poise::command(slash_command, guild_only, subcommands("set", "reset", "show"), subcommand_required)]
+pub async fn askconfig(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Change one or more assistant settings
+#[poise::command(slash_command)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "Model to use, e.g. gpt-4o-mini"]
+    #[autocomplete = "autocomplete_model"]
+    model: Option<String>,
+    #[description = "Instructions the assistant always follows"] system_prompt: Option<String>,
+    #[description = "Character or tone for the assistant, added after the system prompt"] persona: Option<String>,
+    #[description = "Longest answer in tokens (1-4096)"] max_tokens: Option<i32>,
+    #[description = "Randomness from 0 (focused) to 2 (creative)"] temperature: Option<f32>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+
+    let model = model.map(|model| model.trim().to_string());
+    let config = &ctx.data().config.ask;
+    if model.as_deref().is_some_and(|model| config.model(model).is_none()) {
+        let names: Vec<&str> = config.models.iter().map(|model| model.name.as_str()).collect();
+        return Err(Error::Unknown(format!("The model must be one of: {}", names.join(", "))));
+    }
+    if [&system_prompt, &persona].into_iter().flatten().any(|prompt| prompt.chars().count() > MAX_PROMPT_LENGTH) {
+        return Err(Error::Unknown(format!("Prompts can be at most {} characters", MAX_PROMPT_LENGTH)));
+    }
+    if max_tokens.is_some_and(|tokens| !(1..=4096).contains(&tokens)) {
+        return Err(Error::Unknown("Max tokens must be between 1 and 4096".to_string()));
+    }
+    if temperature.is_some_and(|temperature| !(0.0..=2.0).contains(&temperature)) {
+        return Err(Error::Unknown("Temperature must be between 0 and 2".to_string()));
+    }
+
+    let database = &ctx.data().database;
+    let mut settings = database.fetch_ask_settings(guild_id.get() as i64).await?;
+    settings.model = model.or(settings.model);
+    settings.system_prompt = system_prompt.or(settings.system_prompt);
+    settings.persona = persona.or(settings.persona);
+    settings.max_tokens = max_tokens.or(settings.max_tokens);
+    settings.temperature = temperature.or(settings.temperature);
+    database.store_ask_settings(guild_id.get() as i64, &settings).await?;
+
+    show_settings(ctx, "✅ Assistant settings updated.").await
+}
+
+/// Go back to the bot's default for a setting, or for all of them
+#[poise::command(slash_command)]
+pub async fn reset(
+    ctx: Context<'_>,
+    #[description = "Setting to reset, leave empty to reset everything"] setting: Option<AskSetting>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let database = &ctx.data().database;
+
+    match setting {
+        Some(setting) => {
+            let mut settings = database.fetch_ask_settings(guild_id.get() as i64).await?;
+            match setting {
+                AskSetting::Model => settings.model = None,
+                AskSetting::SystemPrompt => settings.system_prompt = None,
+                AskSetting::Persona => settings.persona = None,
+                AskSetting::MaxTokens => settings.max_tokens = None,
+                AskSetting::Temperature => settings.temperature = None,
+            }
+            database.store_ask_settings(guild_id.get() as i64, &settings).await?;
+        }
+        None => database.clear_ask_settings(guild_id.get() as i64).await?,
+    }
+
+    show_settings(ctx, "✅ Assistant settings reset.").await
+}
+
+/// Show the assistant settings in use
+#[poise::command(slash_command)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    show_settings(ctx, "Assistant settings:").await
+}
+
+async fn show_settings(ctx: Context<'_>, heading: &str) -> Result<(), Error> {
+    let options = AskOptions::for_guild(ctx.data(), ctx.guild_id()).await?;
+
+    let content = format!(
+        "{}\n**Model:** {}\n**Max tokens:** {}\n**Temperature:** {}\n**System prompt:** {}\n**Persona:** {}",
+        heading,
+        options.model,
+        options.max_tokens,
+        options.temperature,
+        preview(&options.system_prompt),
+        options.persona.as_deref().map_or("none".to_string(), preview),
+    );
+
+    ctx.send(CreateReply::default()
+        .content(content)
+        .ephemeral(true)
+        .allowed_mentions(CreateAllowedMentions::new()))
+        .await?;
+
+    Ok(())
+}
+
+async fn autocomplete_model(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase();
+    ctx.data().config.ask.models.iter()
+        .filter(|model| model.name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|model| model.name.clone())
+        .collect()
+}
+
+/// Shortens a prompt so two of them fit in one message
+fn preview(prompt: &str) -> String {
+    const PREVIEW_LENGTH: usize = 800;
+    match prompt.char_indices().nth(PREVIEW_LENGTH) {
+        Some((end, _)) => format!("{}…", &prompt[..end]),
+        None => prompt.to_string(),
+    }
+}
\ No newline at end of file
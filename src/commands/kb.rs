@@ -0,0 +1,130 @@
+// commands/kb.rs
+use crate::ai::knowledge;
+use crate::error::Error;
+use crate::messages;
+use crate::Data;
+use poise::serenity_prelude::{Attachment, CreateAllowedMentions};
+use poise::CreateReply;
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Longest name a document can be listed under
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Manage the guides the AI assistant answers from
+#[poise::command(slash_command, guild_only, subcommands("add", "list", "remove"), subcommand_required)]
+pub async fn kb(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add a guide, such as raid strats or server rules, or replace one with the same name
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "A .txt or .md file"] file: Attachment,
+    #[description = "Name to list it under (default: the file name)"] name: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let config = &ctx.data().config.knowledge_base;
+
+    let is_text = [".txt", ".md", ".markdown"].iter().any(|extension| file.filename.to_lowercase().ends_with(extension))
+        || file.content_type.as_deref().is_some_and(|content_type| content_type.starts_with("text/"));
+    if !is_text {
+        return Err(Error::Unknown("Only text and Markdown files can be added".to_string()));
+    }
+    if file.size > config.max_document_bytes {
+        return Err(Error::Unknown(format!("Files can be at most {} KB", config.max_document_bytes / 1024)));
+    }
+    let name = name.unwrap_or_else(|| file.filename.clone()).trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(Error::Unknown(format!("Names must be between 1 and {} characters", MAX_NAME_LENGTH)));
+    }
+
+    ctx.defer().await?;
+
+    let text = String::from_utf8(file.download().await?)
+        .map_err(|_| Error::Unknown("The file isn't UTF-8 text".to_string()))?;
+    if text.trim().is_empty() {
+        return Err(Error::Unknown("The file is empty".to_string()));
+    }
+
+    let chunks = knowledge::embed_document(ctx.data(), guild_id, ctx.author().id, &text).await?;
+    ctx.data().database
+        .store_kb_document(guild_id.get() as i64, &name, ctx.author().id.get() as i64, &chunks)
+        .await?;
+
+    ctx.send(CreateReply::default()
+        .content(format!("✅ Added **{}** to the knowledge base in {} pieces.", name, chunks.len()))
+        .allowed_mentions(CreateAllowedMentions::new()))
+        .await?;
+
+    Ok(())
+}
+
+/// List the guides in the knowledge base
+#[poise::command(slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let documents = ctx.data().database.fetch_kb_documents(guild_id.get() as i64).await?;
+
+    if documents.is_empty() {
+        ctx.say("The knowledge base is empty. Admins can add guides with /kb add.").await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = documents.iter()
+        .map(|document| format!(
+            "**{}** — {} pieces, added by <@{}> <t:{}:R>",
+            document.name,
+            document.chunks,
+            document.added_by,
+            document.created_at.timestamp(),
+        ))
+        .collect();
+
+    messages::reply_long(ctx, &lines.join("\n")).await?;
+
+    Ok(())
+}
+
+/// Remove a guide from the knowledge base
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Guide to remove"]
+    #[autocomplete = "autocomplete_document"]
+    name: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+
+    let response = if ctx.data().database.remove_kb_document(guild_id.get() as i64, &name).await? {
+        format!("✅ Removed **{}** from the knowledge base.", name)
+    } else {
+        format!("**{}** is not in the knowledge base.", name)
+    };
+    ctx.send(CreateReply::default()
+        .content(response)
+        .allowed_mentions(CreateAllowedMentions::new()))
+        .await?;
+
+    Ok(())
+}
+
+async fn autocomplete_document(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Vec::new();
+    };
+    let Ok(documents) = ctx.data().database.fetch_kb_documents(guild_id.get() as i64).await else {
+        return Vec::new();
+    };
+
+    let partial = partial.to_lowercase();
+    documents.into_iter()
+        .filter(|document| document.name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|document| document.name)
+        .collect()
+}
\ No newline at end of file
@@ -0,0 +1,48 @@
+// commands/lockdown.rs
+use crate::error::Error;
+use crate::lockdown;
+use crate::Data;
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Manually control the server lockdown
+#[poise::command(slash_command, guild_only, subcommands("on", "off"), subcommand_required)]
+pub async fn lockdown(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Lock the server down
+#[poise::command(slash_command)]
+pub async fn on(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let reason = format!("Manual lockdown by {}", ctx.author().name);
+
+    if lockdown::engage(ctx.serenity_context(), ctx.data(), guild_id, &reason).await? {
+        ctx.say("🔒 Server is now locked down.").await?;
+    } else {
+        ctx.say("The server is already locked down.").await?;
+    }
+
+    Ok(())
+}
+
+/// Lift the server lockdown
+#[poise::command(slash_command)]
+pub async fn off(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let reason = format!("Lifted by {}", ctx.author().name);
+
+    if lockdown::lift(ctx.serenity_context(), ctx.data(), guild_id, &reason).await? {
+        ctx.say("🔓 Lockdown lifted and channel permissions restored.").await?;
+    } else {
+        ctx.say("The server is not locked down.").await?;
+    }
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,100 @@
+// commands/message_actions.rs
+use crate::ai;
+use crate::error::Error;
+use crate::Data;
+use poise::serenity_prelude::Message;
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Told to members when moderation flags the message they picked
+const MESSAGE_BLOCKED: &str = "That message was flagged by content moderation, so it wasn't sent.";
+
+/// Translate a message into your Discord language
+#[poise::command(context_menu_command = "Translate", ephemeral)]
+pub async fn translate(ctx: Context<'_>, message: Message) -> Result<(), Error> {
+    let language = language_name(ctx.locale());
+    let instructions = format!(
+        "Translate the Discord message you're given into {}. Reply with only the translation, keeping \
+         its formatting, emoji and tone. If it's already in {}, say so briefly in {}.",
+        language, language, language,
+    );
+
+    run(ctx, &message, &instructions, "translation").await
+}
+
+/// Explain what a message means, including slang, jargon and references
+#[poise::command(context_menu_command = "Explain", ephemeral)]
+pub async fn explain(ctx: Context<'_>, message: Message) -> Result<(), Error> {
+    let language = language_name(ctx.locale());
+    let instructions = format!(
+        "Explain what the Discord message you're given means to someone who didn't follow the \
+         conversation. Cover any slang, abbreviations, game jargon or references in it. Keep it \
+         short, and answer in {}.",
+        language,
+    );
+
+    run(ctx, &message, &instructions, "explanation").await
+}
+
+/// Streams the result of `instructions` applied to a message into a private reply. `kind` is
+/// what the result is called in the moderation log.
+async fn run(ctx: Context<'_>, message: &Message, instructions: &str, kind: &str) -> Result<(), Error> {
+    if message.content.trim().is_empty() {
+        return Err(Error::Unknown("That message has no text".to_string()));
+    }
+    ctx.defer_ephemeral().await?;
+
+    if let Some(refusal) = ai::refusal(ctx.data(), ctx.guild_id(), ctx.author().id).await? {
+        ctx.say(refusal).await?;
+        return Ok(());
+    }
+    let request = format!("{} request", kind);
+    if ai::screen(ctx.http(), ctx.data(), ctx.guild_id(), ctx.channel_id(), ctx.author().id, &request, &message.content).await? {
+        ctx.say(MESSAGE_BLOCKED).await?;
+        return Ok(());
+    }
+
+    let mut stream = ai::stream_task(ctx.data(), ctx.guild_id(), instructions, &message.content).await?;
+    let footer = format!("-# {}", message.link());
+    ai::stream_into_reply(ctx, &mut stream, kind, |_| Some(footer)).await?;
+
+    Ok(())
+}
+
+/// The language of a Discord locale, in English for the model's benefit
+fn language_name(locale: Option<&str>) -> &'static str {
+    match locale.unwrap_or("en-US") {
+        "bg" => "Bulgarian",
+        "cs" => "Czech",
+        "da" => "Danish",
+        "de" => "German",
+        "el" => "Greek",
+        "es-ES" => "Spanish",
+        "es-419" => "Latin American Spanish",
+        "fi" => "Finnish",
+        "fr" => "French",
+        "hi" => "Hindi",
+        "hr" => "Croatian",
+        "hu" => "Hungarian",
+        "id" => "Indonesian",
+        "it" => "Italian",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "lt" => "Lithuanian",
+        "nl" => "Dutch",
+        "no" => "Norwegian",
+        "pl" => "Polish",
+        "pt-BR" => "Brazilian Portuguese",
+        "ro" => "Romanian",
+        "ru" => "Russian",
+        "sv-SE" => "Swedish",
+        "th" => "Thai",
+        "tr" => "Turkish",
+        "uk" => "Ukrainian",
+        "vi" => "Vietnamese",
+        "zh-CN" => "Simplified Chinese",
+        "zh-TW" => "Traditional Chinese",
+        "en-GB" => "British English",
+        _ => "English",
+    }
+}
\ No newline at end of file
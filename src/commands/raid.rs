@@ -0,0 +1,373 @@
+// commands/raid.rs
+use crate::commands::raid_catalog::{autocomplete_raid, ensure_in_catalog};
+use crate::commands::timezone::{autocomplete_timezone, resolve_timezone};
+use crate::commands::update_raid_time::{confirm_raid_time, ensure_guild_channel};
+use crate::database::{NewRaidEvent, RaidRecurrence};
+use crate::error::Error;
+use crate::messages;
+use crate::raids::{self, calendar, roster};
+use crate::utils::{parse_time, parse_weekdays, parse_when, DstResolution};
+use crate::Data;
+use chrono::{Duration, Utc, Weekday};
+use poise::serenity_prelude::{AutocompleteChoice, ChannelId, CreateAllowedMentions, CreateAttachment, GuildChannel, Role};
+use poise::CreateReply;
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Maximum number of raids shown by `/raid list`
+const LIST_LIMIT: usize = 20;
+
+/// Manage scheduled raids
+#[poise::command(slash_command, guild_only, subcommands("schedule", "list", "cancel", "roster", "ics", "recurring", "reminderrole"), subcommand_required)]
+pub async fn raid(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Schedule an upcoming raid in a channel
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+pub async fn schedule(
+    ctx: Context<'_>,
+    #[description = "When, e.g. \"next tuesday 8:30pm ET\", \"tomorrow 20:00\" or \"in 3h\""] when: String,
+    #[description = "Timezone if not given in the time (default: yours, see /timezone)"]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: Option<String>,
+    #[description = "Select the raid"]
+    #[autocomplete = "autocomplete_raid"]
+    raid: String,
+    #[description = "Select the channel"] channel: GuildChannel,
+    #[description = "Is this M.I.N.E. or not?"] mine: Option<bool>,
+    #[description = "If clocks change at that time, which time to use (default earlier)"] dst: Option<DstResolution>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    ensure_in_catalog(ctx, &raid).await?;
+
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let channel = ensure_guild_channel(&channel, guild_id)?;
+    let datetime = parse_when(&when, resolve_timezone(ctx, timezone.as_deref()).await?, Utc::now(), dst.unwrap_or(DstResolution::Earliest))?;
+
+    let Some(reply) = confirm_raid_time(ctx, &raid, datetime, channel).await? else {
+        return Ok(());
+    };
+
+    let (id, topic) = raids::schedule(ctx.http(), &ctx.data().database, &NewRaidEvent {
+        guild_id: guild_id.get() as i64,
+        channel_id: channel.get() as i64,
+        raid: &raid,
+        start_time: datetime,
+        mine: mine.unwrap_or(false),
+        created_by: ctx.author().id.get() as i64,
+        recurrence_id: None,
+    }).await?;
+
+    let response = CreateReply::default()
+        .content(format!("Scheduled raid #{} in <#{}>. Channel topic: {}", id, channel, topic))
+        .components(Vec::new())
+        .allowed_mentions(CreateAllowedMentions::new());
+
+    reply.edit(ctx, response).await?;
+
+    Ok(())
+}
+
+/// List the upcoming raids in this server
+#[poise::command(slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let events = ctx.data().database.fetch_upcoming_raid_events(guild_id.get() as i64).await?;
+
+    if events.is_empty() {
+        ctx.say("No upcoming raids scheduled.").await?;
+        return Ok(());
+    }
+
+    let mut lines: Vec<String> = events.iter().take(LIST_LIMIT).map(|event| {
+        format!(
+            "`#{}` **{}** — <t:{}:F> (<t:{}:R>) in <#{}>{}",
+            event.id,
+            event.raid,
+            event.start_time.timestamp(),
+            event.start_time.timestamp(),
+            event.channel_id,
+            if event.mine { " M.I.N.E." } else { "" },
+        )
+    }).collect();
+    if events.len() > LIST_LIMIT {
+        lines.push(format!("…and {} more", events.len() - LIST_LIMIT));
+    }
+
+    messages::reply_long(ctx, &lines.join("\n")).await?;
+
+    Ok(())
+}
+
+/// Cancel a scheduled raid
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+pub async fn cancel(
+    ctx: Context<'_>,
+    #[description = "Raid to cancel"]
+    #[autocomplete = "autocomplete_raid_event"]
+    id: i64,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let Some(event) = ctx.data().database.cancel_raid_event(guild_id.get() as i64, id).await? else {
+        ctx.say(format!("No raid #{} found in this server.", id)).await?;
+        return Ok(());
+    };
+
+    let channel_id = ChannelId::new(event.channel_id as u64);
+    let topic = raids::refresh_channel_topic(ctx.http(), &ctx.data().database, channel_id).await?;
+
+    let response = CreateReply::default()
+        .content(format!("Cancelled raid #{} ({}). Channel topic: {}", event.id, event.raid, topic))
+        .allowed_mentions(CreateAllowedMentions::new());
+
+    ctx.send(response).await?;
+
+    Ok(())
+}
+
+/// Show who has signed up for a raid
+#[poise::command(slash_command)]
+pub async fn roster(
+    ctx: Context<'_>,
+    #[description = "Raid to show"]
+    #[autocomplete = "autocomplete_raid_event"]
+    id: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let Some(event) = ctx.data().database.fetch_raid_event(guild_id.get() as i64, id).await? else {
+        ctx.say(format!("No raid #{} found in this server.", id)).await?;
+        return Ok(());
+    };
+
+    let signups = ctx.data().database.fetch_raid_signups(event.id).await?;
+    let party_size = roster::party_size(&ctx.data().database, guild_id.get() as i64, &event.raid).await?;
+    ctx.send(CreateReply::default().embed(roster::embed(&event, &signups, party_size))).await?;
+
+    Ok(())
+}
+
+/// Download raids as a calendar file
+#[poise::command(slash_command)]
+pub async fn ics(
+    ctx: Context<'_>,
+    #[description = "Raid to export, leave empty for all upcoming raids"]
+    #[autocomplete = "autocomplete_raid_event"]
+    id: Option<i64>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let database = &ctx.data().database;
+    let calendar_config = &ctx.data().config.calendar;
+
+    let (events, filename) = match id {
+        Some(id) => {
+            let Some(event) = database.fetch_raid_event(guild_id.get() as i64, id).await? else {
+                ctx.say(format!("No raid #{} found in this server.", id)).await?;
+                return Ok(());
+            };
+            (vec![event], format!("raid-{}.ics", id))
+        }
+        None => (database.fetch_upcoming_raid_events(guild_id.get() as i64).await?, "raids.ics".to_string()),
+    };
+
+    let name = ctx.guild().map(|guild| format!("{} raids", guild.name)).unwrap_or_else(|| "Raids".to_string());
+    let body = calendar::render_calendar(&name, &events, Duration::minutes(calendar_config.event_duration_minutes));
+
+    let mut content = format!("📅 {} raid(s). Open the file to add them to your calendar.", events.len());
+    if let (true, Some(base_url)) = (calendar_config.feed_enabled, &calendar_config.feed_base_url) {
+        content += &format!(
+            "\nSubscribe to stay up to date: <{}/raids/{}.ics>\nKeep this link within the server, anyone with it can see the raid schedule.",
+            base_url.trim_end_matches('/'),
+            database.fetch_calendar_feed_token(guild_id.get() as i64).await?,
+        );
+    }
+
+    ctx.send(CreateReply::default()
+        .content(content)
+        .attachment(CreateAttachment::bytes(body.into_bytes(), filename))
+        .ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Manage raids that repeat every week
+#[poise::command(
+    slash_command,
+    subcommands("recurring_add", "recurring_list", "recurring_remove"),
+    subcommand_required
+)]
+pub async fn recurring(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Schedule a raid that repeats on the same weekdays and time every week
+#[poise::command(slash_command, rename = "add", required_permissions = "MANAGE_GUILD")]
+pub async fn recurring_add(
+    ctx: Context<'_>,
+    #[description = "Weekdays, e.g. \"Tue, Thu\""] weekdays: String,
+    #[description = "Time of day, e.g. \"8:30pm\" or \"20:30\""] time: String,
+    #[description = "Timezone (default: yours, see /timezone)"]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: Option<String>,
+    #[description = "Select the raid"]
+    #[autocomplete = "autocomplete_raid"]
+    raid: String,
+    #[description = "Select the channel"] channel: GuildChannel,
+    #[description = "Is this M.I.N.E. or not?"] mine: Option<bool>,
+    #[description = "Session length in minutes (default 180)"] duration: Option<i32>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    ensure_in_catalog(ctx, &raid).await?;
+
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let channel = ensure_guild_channel(&channel, guild_id)?;
+    let weekdays = parse_weekdays(&weekdays)?;
+    let local_time = parse_time(&time)?;
+    let timezone = resolve_timezone(ctx, timezone.as_deref()).await?;
+
+    let mut recurrence = RaidRecurrence {
+        id: 0,
+        guild_id: guild_id.get() as i64,
+        channel_id: channel.get() as i64,
+        raid,
+        weekdays: weekdays.iter().map(|day| day.num_days_from_monday() as i16).collect(),
+        local_time,
+        timezone: timezone.name().to_string(),
+        mine: mine.unwrap_or(false),
+        duration_minutes: duration.unwrap_or(180).max(1),
+        created_by: ctx.author().id.get() as i64,
+    };
+    recurrence.id = ctx.data().database.create_raid_recurrence(&recurrence).await?;
+
+    let (_, start_time) = raids::schedule_next_occurrence(ctx.http(), &ctx.data().database, &recurrence).await?;
+
+    let response = CreateReply::default()
+        .content(format!(
+            "Created recurring raid #{}: {}\nFirst raid: <t:{}:F>",
+            recurrence.id,
+            describe_recurrence(&recurrence),
+            start_time.timestamp(),
+        ))
+        .allowed_mentions(CreateAllowedMentions::new());
+
+    ctx.send(response).await?;
+
+    Ok(())
+}
+
+/// List the recurring raids in this server
+#[poise::command(slash_command, rename = "list")]
+pub async fn recurring_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let recurrences = ctx.data().database.fetch_raid_recurrences(guild_id.get() as i64).await?;
+
+    if recurrences.is_empty() {
+        ctx.say("No recurring raids set up.").await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = recurrences.iter()
+        .map(|r| format!("`#{}` {}", r.id, describe_recurrence(r)))
+        .collect();
+
+    messages::reply_long(ctx, &lines.join("\n")).await?;
+
+    Ok(())
+}
+
+/// Stop a recurring raid and cancel its upcoming raids
+#[poise::command(slash_command, rename = "remove", required_permissions = "MANAGE_GUILD")]
+pub async fn recurring_remove(
+    ctx: Context<'_>,
+    #[description = "ID of the recurring raid, see /raid recurring list"] id: i64,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let Some(recurrence) = ctx.data().database.delete_raid_recurrence(guild_id.get() as i64, id).await? else {
+        ctx.say(format!("No recurring raid #{} found in this server.", id)).await?;
+        return Ok(());
+    };
+
+    let channel_id = ChannelId::new(recurrence.channel_id as u64);
+    raids::refresh_channel_topic(ctx.http(), &ctx.data().database, channel_id).await?;
+
+    ctx.send(CreateReply::default()
+        .content(format!("Removed recurring raid #{} ({}).", recurrence.id, recurrence.raid))
+        .allowed_mentions(CreateAllowedMentions::new()))
+        .await?;
+
+    Ok(())
+}
+
+/// Set the role mentioned in raid reminders, or clear it
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+pub async fn reminderrole(
+    ctx: Context<'_>,
+    #[description = "Role to mention, leave empty to stop mentioning a role"] role: Option<Role>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+
+    ctx.data().database
+        .store_raid_reminder_role(guild_id.get() as i64, role.as_ref().map(|r| r.id.get() as i64))
+        .await?;
+
+    let response = match role {
+        Some(role) => format!("✅ Raid reminders will mention <@&{}>.", role.id),
+        None => "✅ Raid reminders will no longer mention a role.".to_string(),
+    };
+    ctx.send(CreateReply::default()
+        .content(response)
+        .allowed_mentions(CreateAllowedMentions::new()))
+        .await?;
+
+    Ok(())
+}
+
+fn describe_recurrence(recurrence: &RaidRecurrence) -> String {
+    let weekdays: Vec<String> = recurrence.weekdays.iter()
+        .filter_map(|day| Weekday::try_from(*day as u8).ok())
+        .map(|day| day.to_string())
+        .collect();
+
+    format!(
+        "**{}** every {} at {} ({}) in <#{}>{}",
+        recurrence.raid,
+        weekdays.join(", "),
+        recurrence.local_time.format("%-I:%M %p"),
+        recurrence.timezone,
+        recurrence.channel_id,
+        if recurrence.mine { " M.I.N.E." } else { "" },
+    )
+}
+
+async fn autocomplete_raid_event(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Vec::new();
+    };
+    let Ok(events) = ctx.data().database.fetch_upcoming_raid_events(guild_id.get() as i64).await else {
+        return Vec::new();
+    };
+
+    let partial = partial.to_lowercase();
+    events.into_iter()
+        .filter(|event| event.id.to_string().starts_with(&partial) || event.raid.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|event| {
+            let name = format!("#{} {} — {}", event.id, event.raid, event.start_time.format("%b %-d %Y %H:%M UTC"));
+            AutocompleteChoice::new(name, event.id)
+        })
+        .collect()
+}
\ No newline at end of file
@@ -0,0 +1,124 @@
+// commands/raid_catalog.rs
+use crate::config::RaidCatalogEntry;
+use crate::error::Error;
+use crate::messages;
+use crate::Data;
+use poise::serenity_prelude::CreateAllowedMentions;
+use poise::CreateReply;
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Manage the raids that can be scheduled in this server
+#[poise::command(slash_command, guild_only, subcommands("add", "remove", "list"), subcommand_required)]
+pub async fn raidcatalog(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add a raid to the catalogue, or update an existing one
+#[poise::command(slash_command)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Name of the raid"] name: String,
+    #[description = "Expansion it's from"] expansion: String,
+    #[description = "Category, e.g. Savage Raid or Extreme Trial"] category: String,
+    #[description = "Party size (default 8)"]
+    #[min = 1]
+    #[max = 40]
+    party_size: Option<i16>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+
+    let entry = RaidCatalogEntry {
+        name: name.trim().to_string(),
+        expansion: expansion.trim().to_string(),
+        category: category.trim().to_string(),
+        party_size: party_size.unwrap_or(8),
+    };
+    ctx.data().database.store_raid_catalog_entry(guild_id.get() as i64, &entry).await?;
+
+    ctx.send(CreateReply::default()
+        .content(format!("✅ Added **{}** to the raid catalogue.", entry.name))
+        .allowed_mentions(CreateAllowedMentions::new()))
+        .await?;
+
+    Ok(())
+}
+
+/// Remove a raid from the catalogue
+#[poise::command(slash_command)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Raid to remove"]
+    #[autocomplete = "autocomplete_raid"]
+    name: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+
+    let response = if ctx.data().database.remove_raid_catalog_entry(guild_id.get() as i64, &name).await? {
+        format!("✅ Removed **{}** from the raid catalogue.", name)
+    } else {
+        format!("**{}** is not in the raid catalogue.", name)
+    };
+    ctx.send(CreateReply::default()
+        .content(response)
+        .allowed_mentions(CreateAllowedMentions::new()))
+        .await?;
+
+    Ok(())
+}
+
+/// List the raids in the catalogue
+#[poise::command(slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let catalog = ctx.data().database.fetch_raid_catalog(guild_id.get() as i64).await?;
+
+    if catalog.is_empty() {
+        ctx.say("The raid catalogue is empty. Use /raidcatalog add to add raids.").await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = catalog.iter()
+        .map(|entry| format!("**{}** — {} {} ({} players)", entry.name, entry.expansion, entry.category, entry.party_size))
+        .collect();
+
+    messages::reply_long(ctx, &lines.join("\n")).await?;
+
+    Ok(())
+}
+
+/// Suggests raids from this server's catalogue
+pub async fn autocomplete_raid(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Vec::new();
+    };
+    let Ok(catalog) = ctx.data().database.fetch_raid_catalog(guild_id.get() as i64).await else {
+        return Vec::new();
+    };
+
+    let partial = partial.to_lowercase();
+    catalog.into_iter()
+        .filter(|entry| {
+            entry.name.to_lowercase().contains(&partial)
+                || entry.expansion.to_lowercase().contains(&partial)
+                || entry.category.to_lowercase().contains(&partial)
+        })
+        .take(25)
+        .map(|entry| entry.name)
+        .collect()
+}
+
+/// Makes sure a raid typed into a command is one from this server's catalogue
+pub async fn ensure_in_catalog(ctx: Context<'_>, raid: &str) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+
+    if ctx.data().database.raid_catalog_contains(guild_id.get() as i64, raid).await? {
+        Ok(())
+    } else {
+        Err(Error::Unknown(format!("{} is not in the raid catalogue. Use /raidcatalog add to add it.", raid)))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,84 @@
+// commands/random_image.rs
+use crate::config::Config;
+use crate::error::Error;
+use crate::Data;
+use poise::serenity_prelude::{CreateAttachment, CreateEmbed, CreateEmbedFooter};
+use poise::CreateReply;
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Providers with a command of their own
+const CAT: &str = "cat";
+const CAPYBARA: &str = "capybara";
+
+/// Get a random animal image
+#[poise::command(slash_command)]
+pub async fn randomimage(
+    ctx: Context<'_>,
+    #[description = "Kind of animal"]
+    #[autocomplete = "autocomplete_provider"]
+    animal: String,
+) -> Result<(), Error> {
+    send_random_image(ctx, &animal).await
+}
+
+/// Get a random cat image
+#[poise::command(slash_command)]
+pub async fn randomcatimage(ctx: Context<'_>) -> Result<(), Error> {
+    send_random_image(ctx, CAT).await
+}
+
+/// Get a random capybara image
+#[poise::command(slash_command)]
+pub async fn randomcapyimage(ctx: Context<'_>) -> Result<(), Error> {
+    send_random_image(ctx, CAPYBARA).await
+}
+
+/// The shortcut commands whose providers are configured. The rest are left out with a
+/// warning, so a `config.toml` without them doesn't leave commands that always fail.
+pub fn shortcuts(config: &Config) -> Vec<poise::Command<Data, Error>> {
+    [(CAT, randomcatimage()), (CAPYBARA, randomcapyimage())]
+        .into_iter()
+        .filter_map(|(name, command)| {
+            if config.image_providers.iter().any(|provider| provider.name.eq_ignore_ascii_case(name)) {
+                return Some(command);
+            }
+            tracing::warn!("No \"{}\" image provider is configured, so /{} isn't registered", name, command.name);
+            None
+        })
+        .collect()
+}
+
+async fn send_random_image(ctx: Context<'_>, name: &str) -> Result<(), Error> {
+    let images = &ctx.data().images;
+    let provider = images.provider(name)
+        .ok_or_else(|| Error::Unknown(format!("There are no {} images. Try one of the suggestions.", name)))?;
+
+    ctx.defer().await?;
+
+    let image = images.random(provider).await?;
+
+    let mut embed = CreateEmbed::new()
+        .title(image.title.unwrap_or_else(|| format!("Random {} image", provider.name)))
+        .image(image.url);
+    if let Some(attribution) = image.attribution {
+        embed = embed.footer(CreateEmbedFooter::new(attribution));
+    }
+
+    let mut reply = CreateReply::default().embed(embed);
+    if let Some(file) = image.file {
+        reply = reply.attachment(CreateAttachment::path(file).await?);
+    }
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
+async fn autocomplete_provider(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase();
+    ctx.data().images.providers().iter()
+        .filter(|provider| provider.name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|provider| provider.name.clone())
+        .collect()
+}
\ No newline at end of file
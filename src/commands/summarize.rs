@@ -0,0 +1,132 @@
+// commands/summarize.rs
+use crate::ai;
+use crate::error::Error;
+use crate::utils::parse_duration;
+use crate::Data;
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::{GetMessages, Message};
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Messages read when neither a count nor a time is given
+const DEFAULT_COUNT: usize = 100;
+/// Most messages read in one go
+const MAX_COUNT: usize = 500;
+/// Most messages Discord returns per request
+const PAGE_SIZE: usize = 100;
+/// Characters kept from each message
+const MAX_LINE_LENGTH: usize = 500;
+/// Characters of transcript sent to the model. The oldest lines are dropped to fit.
+const MAX_TRANSCRIPT_LENGTH: usize = 24_000;
+
+const INSTRUCTIONS: &str = "You summarize Discord conversations. Each line of the transcript is \
+    \"[time] name: message\". Give a short overview, then the key decisions and the action items \
+    with who took them on, as bullet lists under those headings. Leave out a heading with nothing \
+    under it, and don't invent anything that isn't in the transcript.";
+
+/// Told to members when moderation flags the conversation they asked to summarize
+const TRANSCRIPT_BLOCKED: &str = "Part of that conversation was flagged by content moderation, so it wasn't sent.";
+
+/// Summarize the recent conversation in this channel, with decisions and action items
+#[poise::command(slash_command, guild_only, ephemeral)]
+pub async fn summarize(
+    ctx: Context<'_>,
+    #[description = "Number of recent messages to read (default 100, at most 500)"] count: Option<u64>,
+    #[description = "How far back to read, e.g. \"2h\" or \"1 day\""] since: Option<String>,
+) -> Result<(), Error> {
+    if count.is_some_and(|count| count == 0 || count > MAX_COUNT as u64) {
+        return Err(Error::Unknown(format!("Please provide a number between 1 and {}", MAX_COUNT)));
+    }
+    // Spans reaching back before the earliest representable time read everything
+    let cutoff = match &since {
+        Some(since) => Some(Utc::now().checked_sub_signed(parse_duration(since)?).unwrap_or(DateTime::<Utc>::MIN_UTC)),
+        None => None,
+    };
+    // A time on its own reads as far back as it says, up to the maximum
+    let count = match (count, cutoff) {
+        (Some(count), _) => count as usize,
+        (None, Some(_)) => MAX_COUNT,
+        (None, None) => DEFAULT_COUNT,
+    };
+
+    ctx.defer_ephemeral().await?;
+
+    if let Some(refusal) = ai::refusal(ctx.data(), ctx.guild_id(), ctx.author().id).await? {
+        ctx.say(refusal).await?;
+        return Ok(());
+    }
+
+    let channel_id = ctx.channel_id();
+    let mut history: Vec<Message> = Vec::new();
+    let mut before = None;
+    while history.len() < count {
+        let limit = (count - history.len()).min(PAGE_SIZE);
+        let mut request = GetMessages::default().limit(limit as u8);
+        if let Some(before) = before {
+            request = request.before(before);
+        }
+        // Pages come newest first, so each one continues from the oldest message of the last
+        let page = channel_id.messages(&ctx.http(), request).await?;
+        before = page.last().map(|message| message.id);
+
+        let fetched = page.len();
+        let in_range: Vec<Message> = page.into_iter()
+            .take_while(|message| cutoff.is_none_or(|cutoff| *message.timestamp >= cutoff))
+            .collect();
+        let reached_cutoff = in_range.len() < fetched;
+        history.extend(in_range);
+
+        if fetched < limit || reached_cutoff {
+            break;
+        }
+    }
+
+    let transcript = build_transcript(&history);
+    if transcript.is_empty() {
+        ctx.say("There's nothing to summarize in that range.").await?;
+        return Ok(());
+    }
+
+    if ai::screen(ctx.http(), ctx.data(), ctx.guild_id(), ctx.channel_id(), ctx.author().id, "summary request", &transcript).await? {
+        ctx.say(TRANSCRIPT_BLOCKED).await?;
+        return Ok(());
+    }
+
+    let mut stream = ai::stream_task(ctx.data(), ctx.guild_id(), INSTRUCTIONS, &transcript).await?;
+    let footer = format!("-# Summary of {} messages", history.len());
+    ai::stream_into_reply(ctx, &mut stream, "summary", |_| Some(footer)).await?;
+
+    Ok(())
+}
+
+/// Turns messages, newest first, into "[time] name: text" lines, oldest first. Bots and empty
+/// messages are left out, and the oldest lines are dropped if the whole would be too long.
+fn build_transcript(history: &[Message]) -> String {
+    let mut lines = Vec::new();
+    let mut length = 0;
+
+    for message in history {
+        if message.author.bot {
+            continue;
+        }
+        let mut text: String = message.content.chars().take(MAX_LINE_LENGTH).collect();
+        if !message.attachments.is_empty() {
+            text = format!("{} [{} attachment(s)]", text, message.attachments.len());
+        }
+        let text = text.trim().replace('\n', " ");
+        if text.is_empty() {
+            continue;
+        }
+
+        let name = message.author.global_name.as_deref().unwrap_or(&message.author.name);
+        let line = format!("[{}] {}: {}", message.timestamp.format("%Y-%m-%d %H:%M"), name, text);
+        length += line.len() + 1;
+        if length > MAX_TRANSCRIPT_LENGTH {
+            break;
+        }
+        lines.push(line);
+    }
+
+    lines.reverse();
+    lines.join("\n")
+}
\ No newline at end of file
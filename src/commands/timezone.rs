@@ -0,0 +1,112 @@
+// commands/timezone.rs
+use crate::error::Error;
+use crate::utils::parse_timezone;
+use crate::Data;
+use chrono::Utc;
+use chrono_tz::{Tz, TZ_VARIANTS};
+use poise::CreateReply;
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Timezone used when neither the user nor the server has picked one
+const FALLBACK_TIMEZONE: Tz = Tz::America__New_York;
+
+/// Manage the timezone times are read in
+#[poise::command(slash_command, guild_only, subcommands("set", "guild", "show"), subcommand_required)]
+pub async fn timezone(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set your own timezone
+#[poise::command(slash_command)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "Timezone, e.g. Europe/London or Australia/Sydney"]
+    #[autocomplete = "autocomplete_timezone"]
+    zone: String,
+) -> Result<(), Error> {
+    let tz = parse_zone(&zone)?;
+    ctx.data().database.store_user_timezone(ctx.author().id.get() as i64, tz.name()).await?;
+
+    ctx.send(CreateReply::default()
+        .content(format!("✅ Your timezone is now **{}** (currently {}).", tz.name(), local_now(tz)))
+        .ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Set the default timezone for this server
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+pub async fn guild(
+    ctx: Context<'_>,
+    #[description = "Timezone, e.g. America/New_York or Europe/Berlin"]
+    #[autocomplete = "autocomplete_timezone"]
+    zone: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+
+    let tz = parse_zone(&zone)?;
+    ctx.data().database.store_guild_timezone(guild_id.get() as i64, tz.name()).await?;
+
+    ctx.say(format!("✅ This server's default timezone is now **{}**.", tz.name())).await?;
+
+    Ok(())
+}
+
+/// Show the timezone your times are read in
+#[poise::command(slash_command)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    let tz = resolve_timezone(ctx, None).await?;
+    ctx.send(CreateReply::default()
+        .content(format!(
+            "Times you enter are read as **{}** (currently {}). Change it with /timezone set.",
+            tz.name(),
+            local_now(tz),
+        ))
+        .ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Picks the timezone for a command: the one given explicitly, then the user's preference,
+/// then the server default, then Eastern
+pub async fn resolve_timezone(ctx: Context<'_>, explicit: Option<&str>) -> Result<Tz, Error> {
+    if let Some(zone) = explicit {
+        return parse_zone(zone);
+    }
+
+    let database = &ctx.data().database;
+    if let Some(zone) = database.fetch_user_timezone(ctx.author().id.get() as i64).await? {
+        return Ok(zone.parse()?);
+    }
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Some(zone) = database.fetch_guild_timezone(guild_id.get() as i64).await? {
+            return Ok(zone.parse()?);
+        }
+    }
+
+    Ok(FALLBACK_TIMEZONE)
+}
+
+/// Suggests IANA timezone names
+pub async fn autocomplete_timezone(_ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase().replace(' ', "_");
+    TZ_VARIANTS.iter()
+        .map(|tz| tz.name())
+        .filter(|name| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_zone(zone: &str) -> Result<Tz, Error> {
+    parse_timezone(zone.trim())
+        .ok_or_else(|| Error::Unknown(format!("Unknown timezone \"{}\". Pick one from the list, e.g. Europe/London.", zone.trim())))
+}
+
+fn local_now(tz: Tz) -> String {
+    Utc::now().with_timezone(&tz).format("%H:%M %Z").to_string()
+}
\ No newline at end of file
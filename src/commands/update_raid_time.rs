@@ -1,173 +1,124 @@
 // commands/update_raid_time.rs
 use crate::error::Error;
 use crate::Data;
-use crate::utils::parse_datetime;
-use poise::serenity_prelude::{ChannelId, CreateAllowedMentions};
-use poise::serenity_prelude::EditChannel;
+use crate::commands::raid_catalog::{autocomplete_raid, ensure_in_catalog};
+use crate::commands::timezone::{autocomplete_timezone, resolve_timezone};
+use crate::database::NewRaidEvent;
+use crate::raids;
+use crate::utils::{parse_when, DstResolution};
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::{
+    ButtonStyle, ChannelId, ComponentInteractionCollector, CreateActionRow, CreateAllowedMentions,
+    CreateButton, CreateInteractionResponse, GuildChannel, GuildId,
+};
+use poise::{CreateReply, ReplyHandle};
+use std::time::Duration;
 
 type Context<'a> = poise::Context<'a, Data, Error>;
 
-#[derive(poise::ChoiceParameter, Debug)]
-pub enum Month {
-    January, February, March, April, May, June, July,
-    August, September, October, November, December
-}
-
-#[derive(poise::ChoiceParameter)]
-pub enum Time {
-    #[name = "12:00 AM"] T1200AM,
-    #[name = "1:00 AM"] T0100AM,
-    #[name = "2:00 AM"] T0200AM,
-    #[name = "3:00 AM"] T0300AM,
-    #[name = "4:00 AM"] T0400AM,
-    #[name = "5:00 AM"] T0500AM,
-    #[name = "6:00 AM"] T0600AM,
-    #[name = "7:00 AM"] T0700AM,
-    #[name = "8:00 AM"] T0800AM,
-    #[name = "9:00 AM"] T0900AM,
-    #[name = "10:00 AM"] T1000AM,
-    #[name = "11:00 AM"] T1100AM,
-    #[name = "12:00 PM"] T1200PM,
-    #[name = "1:00 PM"] T0100PM,
-    #[name = "2:00 PM"] T0200PM,
-    #[name = "3:00 PM"] T0300PM,
-    #[name = "4:00 PM"] T0400PM,
-    #[name = "5:00 PM"] T0500PM,
-    #[name = "6:00 PM"] T0600PM,
-    #[name = "7:00 PM"] T0700PM,
-    #[name = "8:00 PM"] T0800PM,
-    #[name = "9:00 PM"] T0900PM,
-    #[name = "10:00 PM"] T1000PM,
-    #[name = "11:00 PM"] T1100PM,
-}
-
-#[derive(poise::ChoiceParameter)]
-pub enum Year {
-    #[name = "2024"] Y2024 = 2024,
-    #[name = "2025"] Y2025 = 2025,
-    #[name = "2026"] Y2026 = 2026,
-    #[name = "2027"] Y2027 = 2027,
-    #[name = "2028"] Y2028 = 2028,
-    #[name = "2029"] Y2029 = 2029,
-    #[name = "2030"] Y2030 = 2030,
-}
+/// Update the raid time in a channel's topic, replacing any raids already scheduled there
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn updateraidtime(
+    ctx: Context<'_>,
+    #[description = "When, e.g. \"next tuesday 8:30pm ET\", \"tomorrow 20:00\" or \"in 3h\""] when: String,
+    #[description = "Timezone if not given in the time (default: yours, see /timezone)"]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: Option<String>,
+    #[description = "Select the raid"]
+    #[autocomplete = "autocomplete_raid"]
+    raid: String,
+    #[description = "Select the channel"] channel: GuildChannel,
+    #[description = "Is this M.I.N.E. or not?"] mine: Option<bool>,
+    #[description = "If clocks change at that time, which time to use (default earlier)"] dst: Option<DstResolution>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    ensure_in_catalog(ctx, &raid).await?;
+
+    let guild_id = ctx.guild_id()
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+    let channel = ensure_guild_channel(&channel, guild_id)?;
+    let datetime = parse_when(&when, resolve_timezone(ctx, timezone.as_deref()).await?, Utc::now(), dst.unwrap_or(DstResolution::Earliest))?;
+
+    let Some(reply) = confirm_raid_time(ctx, &raid, datetime, channel).await? else {
+        return Ok(());
+    };
+
+    ctx.data().database.cancel_channel_raid_events(channel.get() as i64).await?;
+    let (_, topic) = raids::schedule(ctx.http(), &ctx.data().database, &NewRaidEvent {
+        guild_id: guild_id.get() as i64,
+        channel_id: channel.get() as i64,
+        raid: &raid,
+        start_time: datetime,
+        mine: mine.unwrap_or(false),
+        created_by: ctx.author().id.get() as i64,
+        recurrence_id: None,
+    }).await?;
+
+    let response = CreateReply::default()
+        .content(format!("Updated channel topic: {}", topic))
+        .components(Vec::new())
+        .allowed_mentions(CreateAllowedMentions::new());
 
-#[derive(poise::ChoiceParameter)]
-pub enum Timezone {
-    #[name = "Eastern Time"] ET,
-    #[name = "Central Time"] CT,
-    #[name = "Mountain Time"] MT,
-    #[name = "Pacific Time"] PT,
-    #[name = "Alaska Time"] AKT,
-    #[name = "Hawaii Standard Time"] HST,
-}
+    reply.edit(ctx, response).await?;
 
-#[derive(poise::ChoiceParameter)]
-pub enum Raid {
-    #[name = "Alexander - The Burden of the Son (Savage)"]
-    AlexanderBurdenSavage,
-    #[name = "Alexander - The Eyes of the Creator (Savage)"]
-    AlexanderEyesSavage,
-    #[name = "Alexander - The Breath of the Creator (Savage)"]
-    AlexanderBreathSavage,
-    #[name = "Alexander - The Heart of the Creator (Savage)"]
-    AlexanderHeartSavage,
-    #[name = "Alexander - The Soul of the Creator (Savage)"]
-    AlexanderSoulSavage,
+    Ok(())
 }
 
-impl std::fmt::Display for Month {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+/// The channel's ID, or an error if it belongs to another server. Discord only offers the
+/// server's own channels, but a crafted interaction can name any channel the bot can see.
+pub fn ensure_guild_channel(channel: &GuildChannel, guild_id: GuildId) -> Result<ChannelId, Error> {
+    if channel.guild_id != guild_id {
+        return Err(Error::Unknown("That channel isn't in this server".to_string()));
     }
+    Ok(channel.id)
 }
 
-impl Time {
-    fn to_string(&self) -> String {
-        match self {
-            Time::T1200AM => "12:00 AM".to_string(),
-            Time::T0100AM => "1:00 AM".to_string(),
-            Time::T0200AM => "2:00 AM".to_string(),
-            Time::T0300AM => "3:00 AM".to_string(),
-            Time::T0400AM => "4:00 AM".to_string(),
-            Time::T0500AM => "5:00 AM".to_string(),
-            Time::T0600AM => "6:00 AM".to_string(),
-            Time::T0700AM => "7:00 AM".to_string(),
-            Time::T0800AM => "8:00 AM".to_string(),
-            Time::T0900AM => "9:00 AM".to_string(),
-            Time::T1000AM => "10:00 AM".to_string(),
-            Time::T1100AM => "11:00 AM".to_string(),
-            Time::T1200PM => "12:00 PM".to_string(),
-            Time::T0100PM => "1:00 PM".to_string(),
-            Time::T0200PM => "2:00 PM".to_string(),
-            Time::T0300PM => "3:00 PM".to_string(),
-            Time::T0400PM => "4:00 PM".to_string(),
-            Time::T0500PM => "5:00 PM".to_string(),
-            Time::T0600PM => "6:00 PM".to_string(),
-            Time::T0700PM => "7:00 PM".to_string(),
-            Time::T0800PM => "8:00 PM".to_string(),
-            Time::T0900PM => "9:00 PM".to_string(),
-            Time::T1000PM => "10:00 PM".to_string(),
-            Time::T1100PM => "11:00 PM".to_string(),
+/// Shows the resolved raid time and waits for the author to confirm it. Returns the reply to
+/// edit with the outcome, or `None` if they cancelled or didn't answer within a minute.
+pub async fn confirm_raid_time<'a>(
+    ctx: Context<'a>,
+    raid: &str,
+    start_time: DateTime<Utc>,
+    channel: ChannelId,
+) -> Result<Option<ReplyHandle<'a>>, Error> {
+    let confirm_id = format!("{}confirm", ctx.id());
+    let cancel_id = format!("{}cancel", ctx.id());
+
+    let preview = CreateReply::default()
+        .content(format!(
+            "**{}** in <#{}> at <t:{}:F> (<t:{}:R>). Is that right?",
+            raid,
+            channel,
+            start_time.timestamp(),
+            start_time.timestamp(),
+        ))
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(&confirm_id).label("Confirm").style(ButtonStyle::Success),
+            CreateButton::new(&cancel_id).label("Cancel").style(ButtonStyle::Secondary),
+        ])])
+        .allowed_mentions(CreateAllowedMentions::new());
+    let reply = ctx.send(preview).await?;
+    let message = reply.message().await?;
+
+    let interaction = ComponentInteractionCollector::new(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .message_id(message.id)
+        .timeout(Duration::from_secs(60))
+        .await;
+
+    match interaction {
+        Some(interaction) if interaction.data.custom_id == confirm_id => {
+            interaction.create_response(ctx, CreateInteractionResponse::Acknowledge).await?;
+            Ok(Some(reply))
         }
-    }
-}
-
-impl Timezone {
-    fn as_ref(&self) -> &'static str {
-        match self {
-            Timezone::ET => "America/New_York",
-            Timezone::CT => "America/Chicago",
-            Timezone::MT => "America/Denver",
-            Timezone::PT => "America/Los_Angeles",
-            Timezone::AKT => "America/Anchorage",
-            Timezone::HST => "America/Honolulu",
+        Some(interaction) => {
+            interaction.create_response(ctx, CreateInteractionResponse::Acknowledge).await?;
+            reply.edit(ctx, CreateReply::default().content("Cancelled.").components(Vec::new())).await?;
+            Ok(None)
         }
-    }
-}
-
-impl Raid {
-    fn as_ref(&self) -> &'static str {
-        match self {
-            Raid::AlexanderBurdenSavage => "Alexander - The Burden of the Son (Savage)",
-            Raid::AlexanderEyesSavage => "Alexander - The Eyes of the Creator (Savage)",
-            Raid::AlexanderBreathSavage => "Alexander - The Breath of the Creator (Savage)",
-            Raid::AlexanderHeartSavage => "Alexander - The Heart of the Creator (Savage)",
-            Raid::AlexanderSoulSavage => "Alexander - The Soul of the Creator (Savage)",
+        None => {
+            reply.edit(ctx, CreateReply::default().content("Timed out, nothing was changed.").components(Vec::new())).await?;
+            Ok(None)
         }
     }
-}
-
-/// Update the raid time in a channel's topic
-#[poise::command(slash_command)]
-pub async fn updateraidtime(
-    ctx: Context<'_>,
-    #[description = "Select the month"] month: Month,
-    #[description = "Enter the day"] day: i64,
-    #[description = "Select the year"] year: Year,
-    #[description = "Select the time"] time: Time,
-    #[description = "Select the timezone"] timezone: Timezone,
-    #[description = "Select the raid"] raid: Raid,
-    #[description = "Select the channel"] channel: ChannelId,
-    #[description = "Is this M.I.N.E. or not?"] mine: Option<bool>,
-) -> Result<(), Error> {
-    ctx.defer().await?;
-
-    let datetime = parse_datetime(&month.to_string(), day, year as i64, &time.to_string(), timezone.as_ref())?;
-    let unix_timestamp = datetime.timestamp();
-
-    let mut topic = format!("Next Meet Is: {} | Time: <t:{}:f>", raid.as_ref(), unix_timestamp);
-    if mine.unwrap_or(false) {
-        topic += " M.I.N.E.";
-    }
-
-    ctx.http().edit_channel(channel, &EditChannel::new().topic(&topic), None).await?;
-
-    let response = poise::CreateReply::default()
-        .content(format!("Updated channel topic: {}", topic))
-        .allowed_mentions(CreateAllowedMentions::new());
-
-    ctx.send(response).await?;
-
-    Ok(())
 }
\ No newline at end of file
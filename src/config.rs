@@ -7,7 +7,320 @@ use tokio::io::AsyncReadExt;
 pub struct Config {
     pub bot_token: String,
     pub database_url: String,
+    /// Not needed when `llm.provider` points at a local server that doesn't check keys
+    #[serde(default)]
     pub openai_api_key: String,
+    #[serde(default)]
+    pub llm: LlmConfig,
+    #[serde(default)]
+    pub lockdown: LockdownConfig,
+    #[serde(default)]
+    pub raid_reminders: RaidReminderConfig,
+    /// Raids every guild's catalogue starts out with
+    #[serde(default = "default_raid_catalog")]
+    pub raid_catalog: Vec<RaidCatalogEntry>,
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+    #[serde(default)]
+    pub ask: AskConfig,
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    #[serde(default)]
+    pub knowledge_base: KnowledgeBaseConfig,
+    /// APIs `/randomimage` can pick from
+    #[serde(default = "default_image_providers")]
+    pub image_providers: Vec<ImageProviderConfig>,
+    /// Prefetching and bundled fallbacks for `/randomimage`
+    #[serde(default)]
+    pub image_pool: ImagePoolConfig,
+}
+
+/// Thresholds for the automatic anti-raid lockdown
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LockdownConfig {
+    pub enabled: bool,
+    /// Number of joins within `join_window_secs` that triggers a lockdown
+    pub join_threshold: usize,
+    pub join_window_secs: u64,
+    /// Accounts younger than this many days count as fresh
+    pub fresh_account_days: i64,
+    /// Number of fresh accounts within `join_window_secs` that triggers a lockdown
+    pub fresh_account_threshold: usize,
+    /// How long members joining during a lockdown are timed out for, 0 to disable
+    pub timeout_minutes: i64,
+}
+
+impl Default for LockdownConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            join_threshold: 10,
+            join_window_secs: 30,
+            fresh_account_days: 7,
+            fresh_account_threshold: 5,
+            timeout_minutes: 60,
+        }
+    }
+}
+
+/// When reminders are posted before a raid starts
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RaidReminderConfig {
+    /// Minutes before the start time to post a reminder
+    pub offsets_minutes: Vec<i32>,
+    pub poll_interval_secs: u64,
+}
+
+impl Default for RaidReminderConfig {
+    fn default() -> Self {
+        Self {
+            offsets_minutes: vec![24 * 60, 60, 15],
+            poll_interval_secs: 30,
+        }
+    }
+}
+
+/// Calendar exports of scheduled raids
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// Length given to raids in calendars, since raids only store a start time
+    pub event_duration_minutes: i64,
+    /// Serve a subscribable feed of each guild's raids over HTTP
+    pub feed_enabled: bool,
+    pub feed_bind_address: String,
+    /// Public address of the feed server, used for the links `/raid ics` hands out
+    pub feed_base_url: Option<String>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            event_duration_minutes: 180,
+            feed_enabled: false,
+            feed_bind_address: "0.0.0.0:8080".to_string(),
+            feed_base_url: None,
+        }
+    }
+}
+
+/// Which server the AI features talk to
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LlmConfig {
+    pub provider: LlmProvider,
+    /// Base URL of the API, e.g. "http://localhost:11434/v1" for Ollama. Defaults to OpenAI's.
+    pub api_base: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub enum LlmProvider {
+    /// The OpenAI API
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// A server that mimics the OpenAI API, like llama.cpp, Ollama or vLLM
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+}
+
+/// Defaults for the `/ask` assistant, which each guild can override with `/askconfig`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AskConfig {
+    pub model: String,
+    /// Model used instead of `model` when a question comes with images
+    pub vision_model: String,
+    pub system_prompt: String,
+    /// Extra instructions added after the system prompt, e.g. a character for the bot to play
+    pub persona: Option<String>,
+    pub max_tokens: u32,
+    pub temperature: f32,
+    /// Tokens a member can use in any 24 hours, 0 for no limit
+    pub user_daily_tokens: i64,
+    /// Tokens a whole guild can use in any 24 hours, 0 for no limit
+    pub guild_daily_tokens: i64,
+    /// Seconds a member has to wait between questions
+    pub cooldown_secs: u64,
+    /// Whether the model can look up the bot's data, like upcoming raids. Turn off for models or
+    /// servers without tool calling.
+    pub tools: bool,
+    /// Models guilds can pick with `/askconfig set`, and their prices. Answers from models not
+    /// listed here are estimated to cost nothing.
+    pub models: Vec<AskModelConfig>,
+}
+
+/// A model on the `/ask` allow-list
+#[derive(Clone, Debug, Deserialize)]
+pub struct AskModelConfig {
+    pub name: String,
+    /// US dollars per million prompt tokens, used for spend estimates
+    pub prompt_price_per_million: f64,
+    /// US dollars per million completion tokens, used for spend estimates
+    pub completion_price_per_million: f64,
+}
+
+impl AskConfig {
+    /// The allow-list entry for a model
+    pub fn model(&self, name: &str) -> Option<&AskModelConfig> {
+        self.models.iter().find(|model| model.name == name)
+    }
+}
+
+impl Default for AskConfig {
+    fn default() -> Self {
+        Self {
+            model: "gpt-3.5-turbo".to_string(),
+            vision_model: "gpt-4o-mini".to_string(),
+            system_prompt: "You are a helpful assistant.".to_string(),
+            persona: None,
+            max_tokens: 512,
+            temperature: 1.0,
+            user_daily_tokens: 20_000,
+            guild_daily_tokens: 200_000,
+            cooldown_secs: 10,
+            tools: true,
+            models: vec![
+                AskModelConfig {
+                    name: "gpt-3.5-turbo".to_string(),
+                    prompt_price_per_million: 0.5,
+                    completion_price_per_million: 1.5,
+                },
+                AskModelConfig {
+                    name: "gpt-4o-mini".to_string(),
+                    prompt_price_per_million: 0.15,
+                    completion_price_per_million: 0.6,
+                },
+            ],
+        }
+    }
+}
+
+/// Screening of `/ask` questions and answers
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ModerationConfig {
+    /// Whether to check text with OpenAI's moderation endpoint, which needs `openai_api_key`.
+    /// Defaults to on when a key is set.
+    pub use_openai: Option<bool>,
+    /// Regular expressions for text that's never allowed, e.g. '(?i)\bfree nitro\b'
+    pub blocked_patterns: Vec<String>,
+}
+
+/// The guides `/ask` answers from, added with `/kb add`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct KnowledgeBaseConfig {
+    pub embedding_model: String,
+    /// US dollars per million tokens embedded, for the spend estimate
+    pub price_per_million: f64,
+    /// Characters per piece documents are split into
+    pub chunk_size: usize,
+    /// Most pieces added to a question's prompt
+    pub results: usize,
+    /// How similar a piece has to be to the question to be used, from 0 to 1
+    pub min_similarity: f32,
+    /// Largest file `/kb add` accepts, in bytes
+    pub max_document_bytes: u32,
+}
+
+impl Default for KnowledgeBaseConfig {
+    fn default() -> Self {
+        Self {
+            embedding_model: "text-embedding-3-small".to_string(),
+            price_per_million: 0.02,
+            chunk_size: 1500,
+            results: 4,
+            min_similarity: 0.3,
+            max_document_bytes: 512 * 1024,
+        }
+    }
+}
+
+/// A raid or duty that can be scheduled
+#[derive(Clone, Debug, Deserialize)]
+pub struct RaidCatalogEntry {
+    pub name: String,
+    pub expansion: String,
+    pub category: String,
+    pub party_size: i16,
+}
+
+fn default_raid_catalog() -> Vec<RaidCatalogEntry> {
+    [
+        "Alexander - The Burden of the Son (Savage)",
+        "Alexander - The Eyes of the Creator (Savage)",
+        "Alexander - The Breath of the Creator (Savage)",
+        "Alexander - The Heart of the Creator (Savage)",
+        "Alexander - The Soul of the Creator (Savage)",
+    ]
+    .into_iter()
+    .map(|name| RaidCatalogEntry {
+        name: name.to_string(),
+        expansion: "Heavensward".to_string(),
+        category: "Savage Raid".to_string(),
+        party_size: 8,
+    })
+    .collect()
+}
+
+/// A random image API. Paths pick a value out of its JSON response, with keys and array
+/// indexes separated by dots, e.g. "results.0.url".
+#[derive(Clone, Debug, Deserialize)]
+pub struct ImageProviderConfig {
+    /// What members pick in `/randomimage`, e.g. "cat"
+    pub name: String,
+    pub url: String,
+    pub image_path: String,
+    pub title_path: Option<String>,
+    pub attribution_path: Option<String>,
+    /// Title used when the response doesn't have one
+    pub title: Option<String>,
+}
+
+/// Images kept ready so `/randomimage` doesn't wait on, or fail with, a slow provider
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ImagePoolConfig {
+    /// Images prefetched per provider, 0 to always fetch on demand
+    pub size: usize,
+    pub refresh_interval_secs: u64,
+    /// Directory with a subdirectory of images per provider, e.g. "cat", used when a provider
+    /// is down and nothing is prefetched
+    pub fallback_dir: Option<String>,
+}
+
+impl Default for ImagePoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 5,
+            refresh_interval_secs: 60,
+            fallback_dir: Some("assets/images".to_string()),
+        }
+    }
+}
+
+fn default_image_providers() -> Vec<ImageProviderConfig> {
+    vec![
+        ImageProviderConfig {
+            name: "cat".to_string(),
+            url: "https://api.thecatapi.com/v1/images/search".to_string(),
+            image_path: "0.url".to_string(),
+            title_path: None,
+            attribution_path: None,
+            title: Some("Random Cat Image".to_string()),
+        },
+        ImageProviderConfig {
+            name: "capybara".to_string(),
+            url: "https://api.capy.lol/v1/capybara?json=true".to_string(),
+            image_path: "data.url".to_string(),
+            title_path: Some("data.alt".to_string()),
+            attribution_path: None,
+            title: Some("Random Capybara".to_string()),
+        },
+    ]
 }
 
 impl Config {
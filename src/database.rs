@@ -1,24 +1,193 @@
 // database.rs
-use tokio_postgres::{Client, NoTls};
+use tokio_postgres::{Client, NoTls, Row};
+use crate::config::RaidCatalogEntry;
 use crate::error::Error;
+use chrono::{DateTime, NaiveTime, Utc};
+use tokio::sync::Mutex;
 use serenity::model::guild::Guild;
 use serenity::model::channel::ChannelType;
 
 pub struct Database {
     client: Client,
+    /// A second connection for transactions, which would otherwise take in whatever else is
+    /// running on `client` at the time. Held for the length of each transaction.
+    transaction_client: Mutex<Client>,
 }
 
-impl Database {
-    pub async fn connect(url: &str) -> Result<Self, Error> {
-        let (client, connection) = tokio_postgres::connect(url, NoTls).await?;
+/// What's known about a guild, as stored in `guild_info`
+pub struct GuildInfo {
+    pub guild_name: String,
+    pub owner_id: i64,
+    pub member_count: i32,
+}
 
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("Database connection error: {}", e);
-            }
-        });
+/// Guild settings captured when a lockdown starts, so they can be put back afterwards
+pub struct LockdownState {
+    pub previous_verification_level: i16,
+    pub invites_were_disabled: bool,
+}
 
-        Ok(Self { client })
+/// The `@everyone` overwrite a channel had before the lockdown, `None` if it had none
+pub struct LockdownOverwrite {
+    pub channel_id: i64,
+    pub allow: Option<i64>,
+    pub deny: Option<i64>,
+}
+
+/// A scheduled raid, as stored in `raid_events`
+pub struct RaidEvent {
+    pub id: i64,
+    pub channel_id: i64,
+    pub raid: String,
+    pub start_time: DateTime<Utc>,
+    pub mine: bool,
+}
+
+/// A raid about to be inserted into `raid_events`
+pub struct NewRaidEvent<'a> {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub raid: &'a str,
+    pub start_time: DateTime<Utc>,
+    pub mine: bool,
+    pub created_by: i64,
+    /// The recurring schedule this raid was generated from, if any
+    pub recurrence_id: Option<i64>,
+}
+
+const RAID_EVENT_COLUMNS: &str = "id, channel_id, raid, start_time, mine";
+
+impl From<&Row> for RaidEvent {
+    fn from(row: &Row) -> Self {
+        Self {
+            id: row.get(0),
+            channel_id: row.get(1),
+            raid: row.get(2),
+            start_time: row.get(3),
+            mine: row.get(4),
+        }
+    }
+}
+
+/// A raid whose reminder for `offset_minutes` before the start is due
+pub struct DueRaidReminder {
+    pub event: RaidEvent,
+    pub offset_minutes: i32,
+    pub role_id: Option<i64>,
+}
+
+/// A member's sign-up for a raid, `role` being one of the roster's button keys
+pub struct RaidSignup {
+    pub user_id: i64,
+    pub role: String,
+}
+
+/// A weekly raid schedule, as stored in `raid_recurrences`
+pub struct RaidRecurrence {
+    pub id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub raid: String,
+    /// Days from Monday, 0 to 6
+    pub weekdays: Vec<i16>,
+    pub local_time: NaiveTime,
+    pub timezone: String,
+    pub mine: bool,
+    pub duration_minutes: i32,
+    pub created_by: i64,
+}
+
+const RAID_RECURRENCE_COLUMNS: &str =
+    "id, guild_id, channel_id, raid, weekdays, local_time, timezone, mine, duration_minutes, created_by";
+
+impl From<&Row> for RaidRecurrence {
+    fn from(row: &Row) -> Self {
+        Self {
+            id: row.get(0),
+            guild_id: row.get(1),
+            channel_id: row.get(2),
+            raid: row.get(3),
+            weekdays: row.get(4),
+            local_time: row.get(5),
+            timezone: row.get(6),
+            mine: row.get(7),
+            duration_minutes: row.get(8),
+            created_by: row.get(9),
+        }
+    }
+}
+
+/// One turn of an `/ask` conversation, as stored in `ask_history`
+pub struct AskMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    pub content: String,
+}
+
+/// A guild's overrides of the `[ask]` config, `None` meaning the config value is used
+#[derive(Default)]
+pub struct AskSettings {
+    pub model: Option<String>,
+    pub system_prompt: Option<String>,
+    pub persona: Option<String>,
+    pub max_tokens: Option<i32>,
+    pub temperature: Option<f32>,
+}
+
+/// Tokens used by one `/ask` answer, as stored in `ask_usage`
+pub struct AskUsage<'a> {
+    pub guild_id: Option<i64>,
+    pub user_id: i64,
+    pub model: &'a str,
+    pub prompt_tokens: i32,
+    pub completion_tokens: i32,
+    /// Estimated cost in US dollars
+    pub cost: f64,
+}
+
+/// Totals over a set of `ask_usage` rows
+pub struct AskUsageSummary {
+    pub questions: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost: f64,
+}
+
+impl AskUsageSummary {
+    pub fn total_tokens(&self) -> i64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+/// A guide in a guild's knowledge base, as listed by `/kb list`
+pub struct KbDocument {
+    pub name: String,
+    pub added_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub chunks: i64,
+}
+
+/// One piece of a knowledge base document, as stored in `kb_chunks`
+pub struct KbChunk {
+    pub document: String,
+    pub content: String,
+}
+
+/// Adds or updates a raid catalogue entry, taking the guild ID and the entry's fields in order
+const STORE_RAID_CATALOG_ENTRY: &str =
+    "INSERT INTO raid_catalog (guild_id, name, expansion, category, party_size)
+     VALUES ($1, $2, $3, $4, $5)
+     ON CONFLICT (guild_id, name) DO UPDATE SET
+     expansion = EXCLUDED.expansion,
+     category = EXCLUDED.category,
+     party_size = EXCLUDED.party_size";
+
+impl Database {
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        Ok(Self {
+            client: connect_client(url).await?,
+            transaction_client: Mutex::new(connect_client(url).await?),
+        })
     }
 
     pub async fn fetch_warn_channel(&self, guild_id: i64) -> Result<Option<i64>, Error> {
@@ -136,4 +305,746 @@ impl Database {
             .await?;
         Ok(())
     }
+    pub async fn fetch_lockdown(&self, guild_id: i64) -> Result<Option<LockdownState>, Error> {
+        let row = self.client
+            .query_opt(
+                "SELECT previous_verification_level, invites_were_disabled FROM lockdown_state WHERE guild_id = $1",
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(row.map(|r| LockdownState {
+            previous_verification_level: r.get(0),
+            invites_were_disabled: r.get(1),
+        }))
+    }
+
+    pub async fn store_lockdown(&self, guild_id: i64, state: &LockdownState) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO lockdown_state (guild_id, previous_verification_level, invites_were_disabled)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (guild_id) DO NOTHING",
+                &[&guild_id, &state.previous_verification_level, &state.invites_were_disabled],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn store_lockdown_overwrite(&self, guild_id: i64, overwrite: &LockdownOverwrite) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO lockdown_overwrites (guild_id, channel_id, allow_bits, deny_bits)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (guild_id, channel_id) DO NOTHING",
+                &[&guild_id, &overwrite.channel_id, &overwrite.allow, &overwrite.deny],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_lockdown_overwrites(&self, guild_id: i64) -> Result<Vec<LockdownOverwrite>, Error> {
+        let rows = self.client
+            .query(
+                "SELECT channel_id, allow_bits, deny_bits FROM lockdown_overwrites WHERE guild_id = $1",
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|r| LockdownOverwrite {
+            channel_id: r.get(0),
+            allow: r.get(1),
+            deny: r.get(2),
+        }).collect())
+    }
+
+    pub async fn clear_lockdown(&self, guild_id: i64) -> Result<(), Error> {
+        self.client
+            .execute("DELETE FROM lockdown_overwrites WHERE guild_id = $1", &[&guild_id])
+            .await?;
+        self.client
+            .execute("DELETE FROM lockdown_state WHERE guild_id = $1", &[&guild_id])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn create_raid_event(&self, event: &NewRaidEvent<'_>) -> Result<i64, Error> {
+        let row = self.client
+            .query_one(
+                "INSERT INTO raid_events (guild_id, channel_id, raid, start_time, mine, created_by, recurrence_id)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 RETURNING id",
+                &[&event.guild_id, &event.channel_id, &event.raid, &event.start_time, &event.mine, &event.created_by, &event.recurrence_id],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    pub async fn fetch_upcoming_raid_events(&self, guild_id: i64) -> Result<Vec<RaidEvent>, Error> {
+        let rows = self.client
+            .query(
+                &format!("SELECT {} FROM raid_events WHERE guild_id = $1 AND start_time > now() ORDER BY start_time", RAID_EVENT_COLUMNS),
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(rows.iter().map(RaidEvent::from).collect())
+    }
+
+    pub async fn fetch_channel_raid_events(&self, channel_id: i64) -> Result<Vec<RaidEvent>, Error> {
+        let rows = self.client
+            .query(
+                &format!("SELECT {} FROM raid_events WHERE channel_id = $1 AND start_time > now() ORDER BY start_time", RAID_EVENT_COLUMNS),
+                &[&channel_id],
+            )
+            .await?;
+
+        Ok(rows.iter().map(RaidEvent::from).collect())
+    }
+
+    pub async fn cancel_raid_event(&self, guild_id: i64, id: i64) -> Result<Option<RaidEvent>, Error> {
+        let row = self.client
+            .query_opt(
+                &format!("DELETE FROM raid_events WHERE guild_id = $1 AND id = $2 RETURNING {}", RAID_EVENT_COLUMNS),
+                &[&guild_id, &id],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(RaidEvent::from))
+    }
+
+    pub async fn cancel_channel_raid_events(&self, channel_id: i64) -> Result<u64, Error> {
+        let count = self.client
+            .execute(
+                "DELETE FROM raid_events WHERE channel_id = $1 AND start_time > now()",
+                &[&channel_id],
+            )
+            .await?;
+
+        Ok(count)
+    }
+
+    pub async fn fetch_due_raid_reminders(&self, offsets_minutes: &[i32]) -> Result<Vec<DueRaidReminder>, Error> {
+        let rows = self.client
+            .query(
+                "SELECT e.id, e.channel_id, e.raid, e.start_time, e.mine, o.minutes, s.reminder_role_id
+                 FROM raid_events e
+                 CROSS JOIN unnest($1::int[]) AS o(minutes)
+                 LEFT JOIN raid_settings s ON s.guild_id = e.guild_id
+                 WHERE e.start_time > now()
+                 AND e.start_time - make_interval(mins => o.minutes) <= now()
+                 AND NOT EXISTS (
+                     SELECT 1 FROM raid_reminders_sent r
+                     WHERE r.raid_event_id = e.id AND r.offset_minutes = o.minutes
+                 )
+                 ORDER BY e.id, o.minutes",
+                &[&offsets_minutes],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|r| DueRaidReminder {
+            event: RaidEvent::from(r),
+            offset_minutes: r.get(5),
+            role_id: r.get(6),
+        }).collect())
+    }
+
+    pub async fn mark_raid_reminder_sent(&self, raid_event_id: i64, offset_minutes: i32) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO raid_reminders_sent (raid_event_id, offset_minutes) VALUES ($1, $2)
+                 ON CONFLICT DO NOTHING",
+                &[&raid_event_id, &offset_minutes],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn store_raid_reminder_role(&self, guild_id: i64, role_id: Option<i64>) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO raid_settings (guild_id, reminder_role_id) VALUES ($1, $2)
+                 ON CONFLICT (guild_id) DO UPDATE SET reminder_role_id = EXCLUDED.reminder_role_id",
+                &[&guild_id, &role_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// The secret in a guild's calendar feed link, made the first time it's asked for
+    pub async fn fetch_calendar_feed_token(&self, guild_id: i64) -> Result<String, Error> {
+        let row = self.client
+            .query_one(
+                "INSERT INTO raid_settings (guild_id, feed_token) VALUES ($1, replace(gen_random_uuid()::text, '-', ''))
+                 ON CONFLICT (guild_id) DO UPDATE SET feed_token = COALESCE(raid_settings.feed_token, EXCLUDED.feed_token)
+                 RETURNING feed_token",
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    pub async fn fetch_guild_by_calendar_feed_token(&self, token: &str) -> Result<Option<i64>, Error> {
+        let row = self.client
+            .query_opt(
+                "SELECT guild_id FROM raid_settings WHERE feed_token = $1",
+                &[&token],
+            )
+            .await?;
+
+        Ok(row.map(|r| r.get(0)))
+    }
+
+    pub async fn fetch_raid_event(&self, guild_id: i64, id: i64) -> Result<Option<RaidEvent>, Error> {
+        let row = self.client
+            .query_opt(
+                &format!("SELECT {} FROM raid_events WHERE guild_id = $1 AND id = $2", RAID_EVENT_COLUMNS),
+                &[&guild_id, &id],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(RaidEvent::from))
+    }
+
+    pub async fn store_raid_signup(&self, raid_event_id: i64, user_id: i64, role: &str) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO raid_signups (raid_event_id, user_id, role) VALUES ($1, $2, $3)
+                 ON CONFLICT (raid_event_id, user_id) DO UPDATE SET role = EXCLUDED.role, signed_up_at = now()",
+                &[&raid_event_id, &user_id, &role],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_raid_signups(&self, raid_event_id: i64) -> Result<Vec<RaidSignup>, Error> {
+        let rows = self.client
+            .query(
+                "SELECT user_id, role FROM raid_signups WHERE raid_event_id = $1 ORDER BY signed_up_at",
+                &[&raid_event_id],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|r| RaidSignup {
+            user_id: r.get(0),
+            role: r.get(1),
+        }).collect())
+    }
+
+    pub async fn create_raid_recurrence(&self, recurrence: &RaidRecurrence) -> Result<i64, Error> {
+        let row = self.client
+            .query_one(
+                "INSERT INTO raid_recurrences
+                 (guild_id, channel_id, raid, weekdays, local_time, timezone, mine, duration_minutes, created_by)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                 RETURNING id",
+                &[
+                    &recurrence.guild_id,
+                    &recurrence.channel_id,
+                    &recurrence.raid,
+                    &recurrence.weekdays,
+                    &recurrence.local_time,
+                    &recurrence.timezone,
+                    &recurrence.mine,
+                    &recurrence.duration_minutes,
+                    &recurrence.created_by,
+                ],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    pub async fn fetch_raid_recurrences(&self, guild_id: i64) -> Result<Vec<RaidRecurrence>, Error> {
+        let rows = self.client
+            .query(
+                &format!("SELECT {} FROM raid_recurrences WHERE guild_id = $1 ORDER BY id", RAID_RECURRENCE_COLUMNS),
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(rows.iter().map(RaidRecurrence::from).collect())
+    }
+
+    /// Recurring schedules whose last scheduled session has ended and that have no raid scheduled
+    /// or still in progress. A cancelled session still counts until it would have ended, so it
+    /// isn't scheduled again.
+    pub async fn fetch_raid_recurrences_to_roll(&self) -> Result<Vec<RaidRecurrence>, Error> {
+        let rows = self.client
+            .query(
+                &format!(
+                    "SELECT {} FROM raid_recurrences r
+                     WHERE (r.last_occurrence IS NULL
+                            OR r.last_occurrence + make_interval(mins => r.duration_minutes) <= now())
+                     AND NOT EXISTS (
+                         SELECT 1 FROM raid_events e
+                         WHERE e.recurrence_id = r.id
+                         AND e.start_time + make_interval(mins => r.duration_minutes) > now()
+                     )",
+                    RAID_RECURRENCE_COLUMNS,
+                ),
+                &[],
+            )
+            .await?;
+
+        Ok(rows.iter().map(RaidRecurrence::from).collect())
+    }
+
+    /// Records the session a recurring schedule last scheduled, so it's the one rolled forward from
+    pub async fn store_raid_recurrence_last_occurrence(&self, id: i64, start_time: DateTime<Utc>) -> Result<(), Error> {
+        self.client
+            .execute(
+                "UPDATE raid_recurrences SET last_occurrence = $2 WHERE id = $1",
+                &[&id, &start_time],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes a recurring schedule along with the raids it has scheduled that haven't started yet
+    pub async fn delete_raid_recurrence(&self, guild_id: i64, id: i64) -> Result<Option<RaidRecurrence>, Error> {
+        self.client
+            .execute(
+                "DELETE FROM raid_events WHERE guild_id = $1 AND recurrence_id = $2 AND start_time > now()",
+                &[&guild_id, &id],
+            )
+            .await?;
+
+        let row = self.client
+            .query_opt(
+                &format!("DELETE FROM raid_recurrences WHERE guild_id = $1 AND id = $2 RETURNING {}", RAID_RECURRENCE_COLUMNS),
+                &[&guild_id, &id],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(RaidRecurrence::from))
+    }
+
+    /// Gives a guild the default raid catalogue the first time it is seen. Returns `false` if
+    /// the guild had already been seeded, even if its admins have since emptied the catalogue.
+    pub async fn seed_raid_catalog(&self, guild_id: i64, entries: &[RaidCatalogEntry]) -> Result<bool, Error> {
+        let mut client = self.transaction_client.lock().await;
+        let transaction = client.transaction().await?;
+
+        let seeded = transaction
+            .query_opt(
+                "INSERT INTO raid_settings (guild_id, catalog_seeded) VALUES ($1, TRUE)
+                 ON CONFLICT (guild_id) DO UPDATE SET catalog_seeded = TRUE
+                 WHERE raid_settings.catalog_seeded = FALSE
+                 RETURNING guild_id",
+                &[&guild_id],
+            )
+            .await?;
+
+        if seeded.is_none() {
+            return Ok(false);
+        }
+
+        for entry in entries {
+            transaction
+                .execute(
+                    STORE_RAID_CATALOG_ENTRY,
+                    &[&guild_id, &entry.name, &entry.expansion, &entry.category, &entry.party_size],
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn fetch_raid_catalog(&self, guild_id: i64) -> Result<Vec<RaidCatalogEntry>, Error> {
+        let rows = self.client
+            .query(
+                "SELECT name, expansion, category, party_size FROM raid_catalog
+                 WHERE guild_id = $1 ORDER BY expansion, name",
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|r| RaidCatalogEntry {
+            name: r.get(0),
+            expansion: r.get(1),
+            category: r.get(2),
+            party_size: r.get(3),
+        }).collect())
+    }
+
+    pub async fn raid_catalog_contains(&self, guild_id: i64, name: &str) -> Result<bool, Error> {
+        let row = self.client
+            .query_opt(
+                "SELECT 1 FROM raid_catalog WHERE guild_id = $1 AND name = $2",
+                &[&guild_id, &name],
+            )
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    pub async fn store_raid_catalog_entry(&self, guild_id: i64, entry: &RaidCatalogEntry) -> Result<(), Error> {
+        self.client
+            .execute(
+                STORE_RAID_CATALOG_ENTRY,
+                &[&guild_id, &entry.name, &entry.expansion, &entry.category, &entry.party_size],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_raid_party_size(&self, guild_id: i64, name: &str) -> Result<Option<i16>, Error> {
+        let row = self.client
+            .query_opt(
+                "SELECT party_size FROM raid_catalog WHERE guild_id = $1 AND name = $2",
+                &[&guild_id, &name],
+            )
+            .await?;
+
+        Ok(row.map(|r| r.get(0)))
+    }
+
+    pub async fn remove_raid_catalog_entry(&self, guild_id: i64, name: &str) -> Result<bool, Error> {
+        let count = self.client
+            .execute(
+                "DELETE FROM raid_catalog WHERE guild_id = $1 AND name = $2",
+                &[&guild_id, &name],
+            )
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    pub async fn fetch_user_timezone(&self, user_id: i64) -> Result<Option<String>, Error> {
+        let row = self.client
+            .query_opt("SELECT timezone FROM user_timezones WHERE user_id = $1", &[&user_id])
+            .await?;
+
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    pub async fn store_user_timezone(&self, user_id: i64, timezone: &str) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO user_timezones (user_id, timezone) VALUES ($1, $2)
+                 ON CONFLICT (user_id) DO UPDATE SET timezone = EXCLUDED.timezone",
+                &[&user_id, &timezone],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_guild_timezone(&self, guild_id: i64) -> Result<Option<String>, Error> {
+        let row = self.client
+            .query_opt("SELECT timezone FROM guild_timezones WHERE guild_id = $1", &[&guild_id])
+            .await?;
+
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    pub async fn store_guild_timezone(&self, guild_id: i64, timezone: &str) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO guild_timezones (guild_id, timezone) VALUES ($1, $2)
+                 ON CONFLICT (guild_id) DO UPDATE SET timezone = EXCLUDED.timezone",
+                &[&guild_id, &timezone],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// The most recent `limit` messages of a channel's conversation, oldest first
+    pub async fn fetch_ask_history(&self, channel_id: i64, limit: i64) -> Result<Vec<AskMessage>, Error> {
+        let rows = self.client
+            .query(
+                "SELECT role, content FROM (
+                     SELECT id, role, content FROM ask_history WHERE channel_id = $1 ORDER BY id DESC LIMIT $2
+                 ) recent ORDER BY id",
+                &[&channel_id, &limit],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|r| AskMessage {
+            role: r.get(0),
+            content: r.get(1),
+        }).collect())
+    }
+
+    /// Stores a question and the bot's answer, dropping all but the newest `keep` messages in the channel
+    pub async fn store_ask_exchange(
+        &self,
+        channel_id: i64,
+        question: &str,
+        answer: &str,
+        answer_message_id: i64,
+        keep: i64,
+    ) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO ask_history (channel_id, role, content, message_id)
+                 VALUES ($1, 'user', $2, NULL), ($1, 'assistant', $3, $4)",
+                &[&channel_id, &question, &answer, &answer_message_id],
+            )
+            .await?;
+        self.client
+            .execute(
+                "DELETE FROM ask_history WHERE channel_id = $1 AND id NOT IN (
+                     SELECT id FROM ask_history WHERE channel_id = $1 ORDER BY id DESC LIMIT $2
+                 )",
+                &[&channel_id, &keep],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn clear_ask_history(&self, channel_id: i64) -> Result<u64, Error> {
+        let deleted = self.client
+            .execute("DELETE FROM ask_history WHERE channel_id = $1", &[&channel_id])
+            .await?;
+        Ok(deleted)
+    }
+
+    /// Whether a message is one of the bot's `/ask` answers that can still be replied to
+    pub async fn is_ask_answer(&self, message_id: i64) -> Result<bool, Error> {
+        let row = self.client
+            .query_opt("SELECT 1 FROM ask_history WHERE message_id = $1", &[&message_id])
+            .await?;
+        Ok(row.is_some())
+    }
+
+    pub async fn fetch_ask_settings(&self, guild_id: i64) -> Result<AskSettings, Error> {
+        let row = self.client
+            .query_opt(
+                "SELECT model, system_prompt, persona, max_tokens, temperature FROM ask_settings WHERE guild_id = $1",
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(row.map(|r| AskSettings {
+            model: r.get(0),
+            system_prompt: r.get(1),
+            persona: r.get(2),
+            max_tokens: r.get(3),
+            temperature: r.get(4),
+        }).unwrap_or_default())
+    }
+
+    pub async fn store_ask_settings(&self, guild_id: i64, settings: &AskSettings) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO ask_settings (guild_id, model, system_prompt, persona, max_tokens, temperature)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (guild_id) DO UPDATE SET
+                     model = EXCLUDED.model,
+                     system_prompt = EXCLUDED.system_prompt,
+                     persona = EXCLUDED.persona,
+                     max_tokens = EXCLUDED.max_tokens,
+                     temperature = EXCLUDED.temperature",
+                &[
+                    &guild_id,
+                    &settings.model,
+                    &settings.system_prompt,
+                    &settings.persona,
+                    &settings.max_tokens,
+                    &settings.temperature,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn clear_ask_settings(&self, guild_id: i64) -> Result<(), Error> {
+        self.client
+            .execute("DELETE FROM ask_settings WHERE guild_id = $1", &[&guild_id])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn record_ask_usage(&self, usage: &AskUsage<'_>) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO ask_usage (guild_id, user_id, model, prompt_tokens, completion_tokens, cost)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &usage.guild_id,
+                    &usage.user_id,
+                    &usage.model,
+                    &usage.prompt_tokens,
+                    &usage.completion_tokens,
+                    &usage.cost,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Usage since `since`, narrowed to a guild and/or user when given. Embedding rows have no
+    /// completion tokens and aren't counted as questions.
+    pub async fn fetch_ask_usage(
+        &self,
+        guild_id: Option<i64>,
+        user_id: Option<i64>,
+        since: DateTime<Utc>,
+    ) -> Result<AskUsageSummary, Error> {
+        let row = self.client
+            .query_one(
+                "SELECT COUNT(*) FILTER (WHERE completion_tokens > 0), COALESCE(SUM(prompt_tokens), 0)::BIGINT, COALESCE(SUM(completion_tokens), 0)::BIGINT,
+                        COALESCE(SUM(cost), 0)::DOUBLE PRECISION
+                 FROM ask_usage
+                 WHERE ($1::BIGINT IS NULL OR guild_id = $1)
+                   AND ($2::BIGINT IS NULL OR user_id = $2)
+                   AND created_at >= $3",
+                &[&guild_id, &user_id, &since],
+            )
+            .await?;
+
+        Ok(AskUsageSummary {
+            questions: row.get(0),
+            prompt_tokens: row.get(1),
+            completion_tokens: row.get(2),
+            cost: row.get(3),
+        })
+    }
+
+    pub async fn fetch_guild_info(&self, guild_id: i64) -> Result<Option<GuildInfo>, Error> {
+        let row = self.client
+            .query_opt(
+                "SELECT guild_name, owner_id, member_count FROM guild_info WHERE guild_id = $1",
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(row.map(|row| GuildInfo {
+            guild_name: row.get(0),
+            owner_id: row.get(1),
+            member_count: row.get(2),
+        }))
+    }
+
+    /// Stores a knowledge base document and its embedded chunks, replacing any document of the
+    /// same name
+    pub async fn store_kb_document(
+        &self,
+        guild_id: i64,
+        name: &str,
+        added_by: i64,
+        chunks: &[(String, Vec<f32>)],
+    ) -> Result<(), Error> {
+        let mut client = self.transaction_client.lock().await;
+        let transaction = client.transaction().await?;
+
+        let row = transaction
+            .query_one(
+                "INSERT INTO kb_documents (guild_id, name, added_by)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (guild_id, name) DO UPDATE SET
+                 added_by = EXCLUDED.added_by,
+                 created_at = now()
+                 RETURNING id",
+                &[&guild_id, &name, &added_by],
+            )
+            .await?;
+        let document_id: i64 = row.get(0);
+
+        transaction
+            .execute("DELETE FROM kb_chunks WHERE document_id = $1", &[&document_id])
+            .await?;
+        for (position, (content, embedding)) in chunks.iter().enumerate() {
+            transaction
+                .execute(
+                    "INSERT INTO kb_chunks (document_id, position, content, embedding) VALUES ($1, $2, $3, $4)",
+                    &[&document_id, &(position as i32), content, embedding],
+                )
+                .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn fetch_kb_documents(&self, guild_id: i64) -> Result<Vec<KbDocument>, Error> {
+        let rows = self.client
+            .query(
+                "SELECT d.name, d.added_by, d.created_at, COUNT(c.id)
+                 FROM kb_documents d LEFT JOIN kb_chunks c ON c.document_id = d.id
+                 WHERE d.guild_id = $1
+                 GROUP BY d.id
+                 ORDER BY d.name",
+                &[&guild_id],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| KbDocument {
+            name: row.get(0),
+            added_by: row.get(1),
+            created_at: row.get(2),
+            chunks: row.get(3),
+        }).collect())
+    }
+
+    /// Removes a knowledge base document and its chunks. Returns `false` if there was none.
+    pub async fn remove_kb_document(&self, guild_id: i64, name: &str) -> Result<bool, Error> {
+        let deleted = self.client
+            .execute("DELETE FROM kb_documents WHERE guild_id = $1 AND name = $2", &[&guild_id, &name])
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    pub async fn has_kb_chunks(&self, guild_id: i64) -> Result<bool, Error> {
+        let row = self.client
+            .query_one(
+                "SELECT EXISTS (
+                     SELECT 1 FROM kb_chunks c JOIN kb_documents d ON d.id = c.document_id
+                     WHERE d.guild_id = $1
+                 )",
+                &[&guild_id],
+            )
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// The `limit` pieces of a guild's knowledge base most like `embedding` by cosine
+    /// similarity, best first, leaving out any below `min_similarity`. Pieces embedded by
+    /// another model have a different length and never match.
+    pub async fn search_kb_chunks(
+        &self,
+        guild_id: i64,
+        embedding: &[f32],
+        min_similarity: f32,
+        limit: i64,
+    ) -> Result<Vec<KbChunk>, Error> {
+        let rows = self.client
+            .query(
+                "SELECT d.name, c.content
+                 FROM kb_chunks c
+                 JOIN kb_documents d ON d.id = c.document_id
+                 CROSS JOIN LATERAL (
+                     SELECT (SUM(a * b) / NULLIF(sqrt(SUM(a * a)) * sqrt(SUM(b * b)), 0))::REAL AS score
+                     FROM unnest(c.embedding, $2::REAL[]) AS pair (a, b)
+                 ) s
+                 WHERE d.guild_id = $1
+                   AND cardinality(c.embedding) = cardinality($2::REAL[])
+                   AND s.score >= $3
+                 ORDER BY s.score DESC
+                 LIMIT $4",
+                &[&guild_id, &embedding, &min_similarity, &limit],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| KbChunk {
+            document: row.get(0),
+            content: row.get(1),
+        }).collect())
+    }
+}
+
+async fn connect_client(url: &str) -> Result<Client, Error> {
+    let (client, connection) = tokio_postgres::connect(url, NoTls).await?;
+
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Database connection error: {}", e);
+        }
+    });
+
+    Ok(client)
 }
\ No newline at end of file
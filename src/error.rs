@@ -24,9 +24,30 @@ pub enum Error {
     #[error("Timezone parse error: {0}")]
     TimezoneParse(#[from] chrono_tz::ParseError),
 
+    #[error("\"{0}\" is not a month")]
+    InvalidMonth(String),
+
+    #[error("{0} is not a valid date")]
+    InvalidDate(String),
+
+    #[error("\"{0}\" is not a valid time, try something like 8:30pm or 20:30")]
+    InvalidTime(String),
+
+    #[error("{0} doesn't exist in {1} because the clocks go forward then")]
+    NonexistentLocalTime(chrono::NaiveDateTime, String),
+
+    #[error("{0} happens twice in {1} because the clocks go back then")]
+    AmbiguousLocalTime(chrono::NaiveDateTime, String),
+
+    #[error("Invalid blocked pattern: {0}")]
+    Regex(#[from] regex::Error),
+
     #[error("Request error: {0}")]
     Request(#[from] reqwest::Error),
 
+    #[error("{0} isn't responding right now, try again in a bit")]
+    ProviderUnavailable(String),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
\ No newline at end of file
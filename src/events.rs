@@ -1,7 +1,10 @@
 // events.rs
+use crate::ai;
 use crate::error::Error;
+use crate::lockdown;
+use crate::raids::roster;
 use crate::Data;
-use poise::serenity_prelude::{Context, FullEvent, Guild, GuildId};
+use poise::serenity_prelude::{Context, FullEvent, Guild, GuildId, Interaction};
 use poise::FrameworkContext;
 
 pub async fn handle_event(
@@ -17,6 +20,17 @@ pub async fn handle_event(
         FullEvent::GuildDelete { incomplete, .. } => {
             handle_guild_delete(ctx, incomplete.id, data).await?;
         }
+        FullEvent::GuildMemberAddition { new_member } => {
+            lockdown::handle_member_join(ctx, new_member, data).await?;
+        }
+        FullEvent::Message { new_message } if new_message.referenced_message.is_some() => {
+            ai::handle_reply(ctx, new_message, data).await?;
+        }
+        FullEvent::InteractionCreate { interaction: Interaction::Component(component) }
+            if component.data.custom_id.starts_with(roster::CUSTOM_ID_PREFIX) =>
+        {
+            roster::handle_signup(ctx, component, data).await?;
+        }
         _ => {}
     }
     Ok(())
@@ -32,6 +46,9 @@ async fn handle_guild_create(_ctx: &Context, guild: &Guild, data: &Data) -> Resu
     // Store guild channels in the database
     data.database.store_guild_channels(guild).await?;
 
+    // Give new guilds the default raid catalogue
+    data.database.seed_raid_catalog(guild.id.get() as i64, &data.config.raid_catalog).await?;
+
     Ok(())
 }
 
@@ -0,0 +1,241 @@
+// images.rs
+use crate::config::{Config, ImageProviderConfig};
+use crate::error::Error;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, VecDeque};
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest a whole request can take, so a stuck provider can't hold a command open
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Tries per request, the first included
+const ATTEMPTS: u32 = 3;
+/// Wait before the first retry, doubled for each one after
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Files in a fallback directory that are served
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+
+/// An image picked by a provider
+pub struct Image {
+    /// Where the image is, `attachment://<file name>` for a fallback image
+    pub url: String,
+    /// Caption or source the provider gave with it
+    pub title: Option<String>,
+    /// Who took or made it, if the provider says
+    pub attribution: Option<String>,
+    /// Local file to attach, for a fallback image
+    pub file: Option<PathBuf>,
+}
+
+/// Fetches random images from the public image APIs, over one shared connection pool
+pub struct ImageClient {
+    http: reqwest::Client,
+    providers: Vec<ImageProviderConfig>,
+    pool_size: usize,
+    fallback_dir: Option<PathBuf>,
+    /// Prefetched images by provider name, served before asking the provider
+    pools: Mutex<HashMap<String, VecDeque<Image>>>,
+}
+
+#[derive(Deserialize)]
+struct NekosResponse {
+    results: Vec<NekosImage>,
+}
+
+#[derive(Deserialize)]
+struct NekosImage {
+    url: String,
+    anime_name: Option<String>,
+}
+
+impl ImageClient {
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            providers: config.image_providers.clone(),
+            pool_size: config.image_pool.size,
+            fallback_dir: config.image_pool.fallback_dir.as_ref().map(PathBuf::from),
+            pools: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The configured provider with this name, ignoring case
+    pub fn provider(&self, name: &str) -> Option<&ImageProviderConfig> {
+        self.providers.iter().find(|provider| provider.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn providers(&self) -> &[ImageProviderConfig] {
+        &self.providers
+    }
+
+    /// A random image from a configured provider: a prefetched one if there is one, otherwise
+    /// one fetched now, or one from the fallback directory if the provider is down
+    pub async fn random(&self, provider: &ImageProviderConfig) -> Result<Image, Error> {
+        if let Some(image) = self.pools.lock().unwrap().get_mut(&provider.name).and_then(VecDeque::pop_front) {
+            return Ok(image);
+        }
+
+        match self.fetch(provider).await {
+            Ok(image) => Ok(image),
+            Err(error) => match self.fallback(provider).await {
+                Some(image) => Ok(image),
+                None => Err(error),
+            },
+        }
+    }
+
+    async fn fetch(&self, provider: &ImageProviderConfig) -> Result<Image, Error> {
+        let response: Value = self.get_json(&provider.name, &provider.url).await?;
+        let text_at = |path: &Option<String>| {
+            path.as_deref()
+                .and_then(|path| value_at(&response, path))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+
+        let Some(url) = value_at(&response, &provider.image_path).and_then(Value::as_str) else {
+            tracing::warn!("{} response has no image at {}: {}", provider.name, provider.image_path, response);
+            return Err(Error::ProviderUnavailable(provider.name.clone()));
+        };
+
+        Ok(Image {
+            url: url.to_string(),
+            title: text_at(&provider.title_path).or_else(|| provider.title.clone()),
+            attribution: text_at(&provider.attribution_path),
+            file: None,
+        })
+    }
+
+    /// A random image from the provider's fallback directory, if it has any
+    async fn fallback(&self, provider: &ImageProviderConfig) -> Option<Image> {
+        let dir = self.fallback_dir.as_ref()?.join(&provider.name);
+        let mut entries = tokio::fs::read_dir(&dir).await.ok()?;
+
+        let mut files = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if is_image_file(&path) {
+                files.push(path);
+            }
+        }
+        if files.is_empty() {
+            return None;
+        }
+
+        let path = files.swap_remove(random_index(files.len()));
+        let file_name = path.file_name()?.to_string_lossy().into_owned();
+        tracing::info!("Serving fallback image {} for {}", path.display(), provider.name);
+
+        Some(Image {
+            url: format!("attachment://{}", file_name),
+            title: provider.title.clone(),
+            attribution: None,
+            file: Some(path),
+        })
+    }
+
+    /// Tops each provider's pool back up, moving on from a provider once it fails
+    async fn refill_pools(&self) {
+        for provider in &self.providers {
+            while self.pooled(&provider.name) < self.pool_size {
+                let Ok(image) = self.fetch(provider).await else {
+                    break;
+                };
+                self.pools.lock().unwrap().entry(provider.name.clone()).or_default().push_back(image);
+            }
+        }
+    }
+
+    fn pooled(&self, provider: &str) -> usize {
+        self.pools.lock().unwrap().get(provider).map_or(0, VecDeque::len)
+    }
+
+    pub async fn hug(&self) -> Result<Image, Error> {
+        let response: NekosResponse = self.get_json("nekos.best", "https://nekos.best/api/v2/hug").await?;
+        let image = response.results.into_iter().next()
+            .ok_or_else(|| Error::ProviderUnavailable("nekos.best".to_string()))?;
+
+        Ok(Image { url: image.url, title: image.anime_name, attribution: None, file: None })
+    }
+
+    /// Fetches JSON from a provider, retrying with backoff while it's timing out or having
+    /// server trouble. Any failure ends up as `Error::ProviderUnavailable` naming `provider`.
+    async fn get_json<T: DeserializeOwned>(&self, provider: &str, url: &str) -> Result<T, Error> {
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 1..=ATTEMPTS {
+            match self.try_get_json(url).await {
+                Ok(value) => return Ok(value),
+                Err(error) => {
+                    tracing::warn!("{} request failed (attempt {}/{}): {}", provider, attempt, ATTEMPTS, error);
+                    if !is_transient(&error) {
+                        break;
+                    }
+                }
+            }
+            if attempt < ATTEMPTS {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+
+        Err(Error::ProviderUnavailable(provider.to_string()))
+    }
+
+    async fn try_get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, reqwest::Error> {
+        self.http.get(url).send().await?.error_for_status()?.json().await
+    }
+}
+
+/// Background task that keeps every provider's pool of prefetched images full
+pub async fn run_prefetcher(images: Arc<ImageClient>, interval_secs: u64) {
+    if images.pool_size == 0 {
+        return;
+    }
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+        images.refill_pools().await;
+    }
+}
+
+fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// A random index below `len`, which must not be 0. Good enough for picking a picture.
+fn random_index(len: usize) -> usize {
+    (RandomState::new().hash_one(len) % len as u64) as usize
+}
+
+/// Follows a dotted path of object keys and array indexes into a JSON value
+fn value_at<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').filter(|segment| !segment.is_empty()).try_fold(value, |value, segment| {
+        match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        }
+    })
+}
+
+/// Whether a failed request might work if it's tried again
+fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_timeout()
+        || error.is_connect()
+        || error.status().is_some_and(|status| status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS)
+}
\ No newline at end of file
@@ -0,0 +1,295 @@
+// lockdown.rs
+use crate::config::LockdownConfig;
+use crate::database::{LockdownOverwrite, LockdownState};
+use crate::error::Error;
+use crate::messages;
+use crate::Data;
+use chrono::{Duration as ChronoDuration, Utc};
+use poise::serenity_prelude::{
+    ChannelId, ChannelType, Context, EditGuild, GuildId, Member, PermissionOverwrite,
+    PermissionOverwriteType, Permissions, Timestamp, VerificationLevel,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const INVITES_DISABLED: &str = "INVITES_DISABLED";
+
+/// Permissions denied to `@everyone` in text channels while a lockdown is active
+const LOCKED_PERMISSIONS: Permissions = Permissions::SEND_MESSAGES
+    .union(Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(Permissions::CREATE_PUBLIC_THREADS)
+    .union(Permissions::ADD_REACTIONS);
+
+/// Sliding window of recent joins per guild
+#[derive(Default)]
+pub struct JoinTracker {
+    joins: Mutex<HashMap<GuildId, VecDeque<(Instant, bool)>>>,
+}
+
+impl JoinTracker {
+    /// Records a join and returns a reason if it tipped the guild over one of the thresholds
+    pub fn record(&self, guild_id: GuildId, fresh_account: bool, config: &LockdownConfig) -> Option<String> {
+        self.record_at(guild_id, fresh_account, config, Instant::now())
+    }
+
+    fn record_at(&self, guild_id: GuildId, fresh_account: bool, config: &LockdownConfig, now: Instant) -> Option<String> {
+        let window = Duration::from_secs(config.join_window_secs);
+
+        let mut joins = self.joins.lock().unwrap();
+        let recent = joins.entry(guild_id).or_default();
+        recent.push_back((now, fresh_account));
+        while recent.front().is_some_and(|(at, _)| now.duration_since(*at) > window) {
+            recent.pop_front();
+        }
+
+        let fresh = recent.iter().filter(|(_, fresh)| *fresh).count();
+        let reason = if recent.len() >= config.join_threshold {
+            Some(format!("{} joins in {} seconds", recent.len(), config.join_window_secs))
+        } else if fresh >= config.fresh_account_threshold {
+            Some(format!("{} accounts younger than {} days joined in {} seconds", fresh, config.fresh_account_days, config.join_window_secs))
+        } else {
+            None
+        };
+
+        // Start counting afresh so a single wave only triggers once
+        if reason.is_some() {
+            recent.clear();
+        }
+
+        reason
+    }
+}
+
+/// Whether each guild is locked down, as last read from or written to the database, so joins
+/// don't each have to look it up
+#[derive(Default)]
+pub struct LockdownStates {
+    locked: Mutex<HashMap<GuildId, bool>>,
+}
+
+impl LockdownStates {
+    fn get(&self, guild_id: GuildId) -> Option<bool> {
+        self.locked.lock().unwrap().get(&guild_id).copied()
+    }
+
+    fn set(&self, guild_id: GuildId, locked: bool) {
+        self.locked.lock().unwrap().insert(guild_id, locked);
+    }
+}
+
+async fn is_locked_down(data: &Data, guild_id: GuildId) -> Result<bool, Error> {
+    if let Some(locked) = data.lockdown_states.get(guild_id) {
+        return Ok(locked);
+    }
+    let locked = data.database.fetch_lockdown(guild_id.get() as i64).await?.is_some();
+    data.lockdown_states.set(guild_id, locked);
+    Ok(locked)
+}
+
+/// Checks a new member against the join-rate thresholds, locking the guild down if needed
+pub async fn handle_member_join(ctx: &Context, member: &Member, data: &Data) -> Result<(), Error> {
+    let config = &data.config.lockdown;
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let guild_id = member.guild_id;
+    let account_age = Utc::now() - *member.user.created_at();
+    let fresh_account = account_age < ChronoDuration::days(config.fresh_account_days);
+
+    if let Some(reason) = data.join_tracker.record(guild_id, fresh_account, config) {
+        engage(ctx, data, guild_id, &format!("Automatic lockdown: {}", reason)).await?;
+    }
+
+    if config.timeout_minutes > 0 && is_locked_down(data, guild_id).await? {
+        let until = Utc::now() + ChronoDuration::minutes(config.timeout_minutes);
+        let mut member = member.clone();
+        member.disable_communication_until_datetime(ctx, Timestamp::from(until)).await?;
+    }
+
+    Ok(())
+}
+
+/// Locks the guild down. Returns `false` if it was already locked down.
+pub async fn engage(ctx: &Context, data: &Data, guild_id: GuildId, reason: &str) -> Result<bool, Error> {
+    let db_guild_id = guild_id.get() as i64;
+    if data.database.fetch_lockdown(db_guild_id).await?.is_some() {
+        data.lockdown_states.set(guild_id, true);
+        return Ok(false);
+    }
+
+    let guild = guild_id.to_partial_guild(ctx).await?;
+    let invites_were_disabled = guild.features.iter().any(|f| f == INVITES_DISABLED);
+
+    // Save the previous state first so a failure halfway through can still be lifted
+    data.database.store_lockdown(db_guild_id, &LockdownState {
+        previous_verification_level: u8::from(guild.verification_level) as i16,
+        invites_were_disabled,
+    }).await?;
+    data.lockdown_states.set(guild_id, true);
+
+    let mut features = guild.features.clone();
+    if !invites_were_disabled {
+        features.push(INVITES_DISABLED.to_string());
+    }
+    guild_id.edit(ctx, EditGuild::new()
+        .verification_level(VerificationLevel::High)
+        .features(features)
+        .audit_log_reason(reason))
+        .await?;
+
+    let everyone = guild_id.everyone_role();
+    for (channel_id, channel) in guild_id.channels(ctx).await? {
+        if !matches!(channel.kind, ChannelType::Text | ChannelType::News | ChannelType::Forum) {
+            continue;
+        }
+
+        let previous = channel.permission_overwrites.iter()
+            .find(|o| o.kind == PermissionOverwriteType::Role(everyone));
+
+        data.database.store_lockdown_overwrite(db_guild_id, &LockdownOverwrite {
+            channel_id: channel_id.get() as i64,
+            allow: previous.map(|o| o.allow.bits() as i64),
+            deny: previous.map(|o| o.deny.bits() as i64),
+        }).await?;
+
+        channel_id.create_permission(ctx, PermissionOverwrite {
+            allow: previous.map(|o| o.allow - LOCKED_PERMISSIONS).unwrap_or_default(),
+            deny: previous.map(|o| o.deny).unwrap_or_default() | LOCKED_PERMISSIONS,
+            kind: PermissionOverwriteType::Role(everyone),
+        }).await?;
+    }
+
+    tracing::warn!("Lockdown engaged in guild {}: {}", guild_id, reason);
+    messages::alert_mod_log(&ctx.http, data, guild_id, &format!("🔒 Server lockdown engaged. {}", reason)).await?;
+
+    Ok(true)
+}
+
+/// Lifts a lockdown and restores what `engage` changed. Returns `false` if there was none.
+pub async fn lift(ctx: &Context, data: &Data, guild_id: GuildId, reason: &str) -> Result<bool, Error> {
+    let db_guild_id = guild_id.get() as i64;
+    let Some(state) = data.database.fetch_lockdown(db_guild_id).await? else {
+        data.lockdown_states.set(guild_id, false);
+        return Ok(false);
+    };
+
+    let guild = guild_id.to_partial_guild(ctx).await?;
+    let mut features = guild.features.clone();
+    if !state.invites_were_disabled {
+        features.retain(|f| f != INVITES_DISABLED);
+    }
+    guild_id.edit(ctx, EditGuild::new()
+        .verification_level(VerificationLevel::from(state.previous_verification_level as u8))
+        .features(features)
+        .audit_log_reason(reason))
+        .await?;
+
+    let everyone = PermissionOverwriteType::Role(guild_id.everyone_role());
+    for overwrite in data.database.fetch_lockdown_overwrites(db_guild_id).await? {
+        let channel_id = ChannelId::new(overwrite.channel_id as u64);
+        let result = match (overwrite.allow, overwrite.deny) {
+            (Some(allow), Some(deny)) => {
+                channel_id.create_permission(ctx, PermissionOverwrite {
+                    allow: Permissions::from_bits_truncate(allow as u64),
+                    deny: Permissions::from_bits_truncate(deny as u64),
+                    kind: everyone,
+                }).await
+            }
+            _ => channel_id.delete_permission(ctx, everyone).await,
+        };
+
+        // The channel may have been deleted while locked down
+        if let Err(e) = result {
+            tracing::warn!("Failed to restore permissions in channel {}: {}", channel_id, e);
+        }
+    }
+
+    data.database.clear_lockdown(db_guild_id).await?;
+    data.lockdown_states.set(guild_id, false);
+
+    tracing::info!("Lockdown lifted in guild {}: {}", guild_id, reason);
+    messages::alert_mod_log(&ctx.http, data, guild_id, &format!("🔓 Server lockdown lifted. {}", reason)).await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> LockdownConfig {
+        LockdownConfig {
+            join_threshold: 5,
+            join_window_secs: 30,
+            fresh_account_threshold: 3,
+            ..Default::default()
+        }
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn triggers_once_the_join_threshold_is_reached() {
+        let (tracker, config, guild, start) = (JoinTracker::default(), config(), GuildId::new(1), Instant::now());
+
+        for i in 0..4 {
+            assert_eq!(tracker.record_at(guild, false, &config, start + secs(i)), None);
+        }
+        assert_eq!(tracker.record_at(guild, false, &config, start + secs(4)), Some("5 joins in 30 seconds".to_string()));
+        // The wave was counted, so the next join starts a new count
+        assert_eq!(tracker.record_at(guild, false, &config, start + secs(5)), None);
+    }
+
+    #[test]
+    fn forgets_joins_older_than_the_window() {
+        let (tracker, config, guild, start) = (JoinTracker::default(), config(), GuildId::new(1), Instant::now());
+
+        for i in 0..4 {
+            tracker.record_at(guild, false, &config, start + secs(i * 10));
+        }
+        // Only the joins at 10, 20 and 30 seconds are within 30 seconds of this one
+        assert_eq!(tracker.record_at(guild, false, &config, start + secs(40)), None);
+        assert_eq!(tracker.record_at(guild, false, &config, start + secs(40)), Some("5 joins in 30 seconds".to_string()));
+    }
+
+    #[test]
+    fn counts_a_join_exactly_a_window_old() {
+        let (tracker, config, guild, start) = (JoinTracker::default(), config(), GuildId::new(1), Instant::now());
+
+        tracker.record_at(guild, false, &config, start);
+        for _ in 0..3 {
+            tracker.record_at(guild, false, &config, start + secs(30));
+        }
+        assert!(tracker.record_at(guild, false, &config, start + secs(30)).is_some());
+    }
+
+    #[test]
+    fn triggers_on_fresh_accounts_below_the_join_threshold() {
+        let (tracker, config, guild, start) = (JoinTracker::default(), config(), GuildId::new(1), Instant::now());
+
+        tracker.record_at(guild, true, &config, start);
+        tracker.record_at(guild, false, &config, start + secs(1));
+        tracker.record_at(guild, true, &config, start + secs(2));
+        assert_eq!(
+            tracker.record_at(guild, true, &config, start + secs(3)),
+            Some("3 accounts younger than 7 days joined in 30 seconds".to_string()),
+        );
+    }
+
+    #[test]
+    fn counts_each_guild_separately() {
+        let (tracker, config, start) = (JoinTracker::default(), config(), Instant::now());
+
+        for i in 0..4 {
+            tracker.record_at(GuildId::new(1), false, &config, start + secs(i));
+            tracker.record_at(GuildId::new(2), false, &config, start + secs(i));
+        }
+        assert!(tracker.record_at(GuildId::new(1), false, &config, start + secs(4)).is_some());
+        assert_eq!(tracker.record_at(GuildId::new(3), false, &config, start + secs(4)), None);
+        assert!(tracker.record_at(GuildId::new(2), false, &config, start + secs(4)).is_some());
+    }
+}
\ No newline at end of file
@@ -1,20 +1,37 @@
 // main.rs
+mod ai;
 mod commands;
 mod config;
 mod database;
 mod error;
 mod events;
+mod images;
+mod lockdown;
+mod messages;
+mod raids;
 mod utils;
 
+use crate::ai::backend::{self, LlmBackend};
+use crate::ai::moderation::Moderator;
+use crate::ai::AskCooldowns;
 use crate::config::Config;
 use crate::database::Database;
 use crate::error::Error;
+use crate::images::ImageClient;
+use crate::lockdown::{JoinTracker, LockdownStates};
 use poise::serenity_prelude as serenity;
 use serenity::GatewayIntents;
+use std::sync::Arc;
 
 struct Data {
     config: Config,
-    database: Database,
+    database: Arc<Database>,
+    join_tracker: JoinTracker,
+    lockdown_states: LockdownStates,
+    llm: Arc<dyn LlmBackend>,
+    ask_cooldowns: AskCooldowns,
+    moderator: Moderator,
+    images: Arc<ImageClient>,
 }
 
 #[tokio::main]
@@ -22,13 +39,16 @@ async fn main() -> Result<(), Error> {
     tracing_subscriber::fmt::init();
 
     let config = Config::load().await?;
-    let database = Database::connect(&config.database_url).await?;
+    let database = Arc::new(Database::connect(&config.database_url).await?);
+    let llm = backend::from_config(&config);
+    let moderator = Moderator::from_config(&config)?;
+    let images = Arc::new(ImageClient::from_config(&config)?);
 
     let config_clone = config.clone(); // Clone config here
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: commands::get_commands(),
+            commands: commands::get_commands(&config),
             event_handler: |ctx, event, framework, data| {
                 Box::pin(events::handle_event(ctx, event, framework, data))
             },
@@ -37,15 +57,26 @@ async fn main() -> Result<(), Error> {
         .setup(|ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                tokio::spawn(raids::run_scheduler(ctx.http.clone(), database.clone(), config_clone.raid_reminders.clone()));
+                tokio::spawn(images::run_prefetcher(images.clone(), config_clone.image_pool.refresh_interval_secs));
+                if config_clone.calendar.feed_enabled {
+                    tokio::spawn(raids::calendar::run_feed_server(database.clone(), config_clone.calendar.clone()));
+                }
                 Ok(Data {
                     config: config_clone, // Use the cloned config
                     database,
+                    join_tracker: JoinTracker::default(),
+                    lockdown_states: LockdownStates::default(),
+                    llm,
+                    ask_cooldowns: AskCooldowns::default(),
+                    moderator,
+                    images,
                 })
             })
         })
         .build();
 
-    let mut client = serenity::ClientBuilder::new(&config.bot_token, GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT)
+    let mut client = serenity::ClientBuilder::new(&config.bot_token, GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT | GatewayIntents::GUILD_MEMBERS)
         .framework(framework)
         .await?;
 
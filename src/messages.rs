@@ -0,0 +1,337 @@
+// messages.rs
+use crate::error::Error;
+use crate::Data;
+use poise::serenity_prelude::{
+    ChannelId, CreateAllowedMentions, CreateAttachment, CreateMessage, EditMessage, GuildId, Http, Message, MessageId,
+};
+use poise::{CreateReply, ReplyHandle};
+
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Discord's limit on message length
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+/// Text that would take more messages than this is sent as a file instead
+const MAX_CHUNKS: usize = 4;
+/// Room left on a hard-split line for a reopened code fence and its closing marker
+const FENCE_ALLOWANCE: usize = 100;
+
+const FENCE: &str = "```";
+/// Longest opening fence line reopened as it is. Longer ones are reopened as a bare fence, so the
+/// reopened line and the closing marker always fit in `FENCE_ALLOWANCE`.
+const MAX_FENCE_HEADER: usize = FENCE_ALLOWANCE - FENCE.len() - 2;
+
+/// Splits text into pieces of at most `limit` bytes. Pieces end between paragraphs where
+/// possible, and a code block that has to be split is closed at the end of one piece and
+/// reopened, with its language, at the start of the next.
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+    if text.trim().is_empty() {
+        return vec!["(empty)".to_string()];
+    }
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    // Opening line of the code block `current` ends inside, if any
+    let mut open_fence: Option<String> = None;
+    // Where `current` could be cut between two paragraphs
+    let mut paragraph_break: Option<usize> = None;
+
+    let max_line = limit.saturating_sub(FENCE_ALLOWANCE).max(4);
+    for line in text.lines().flat_map(|line| split_line(line, max_line)) {
+        let closing = if open_fence.is_some() { FENCE.len() + 1 } else { 0 };
+        while !current.is_empty() && current.len() + 1 + line.len() + closing > limit {
+            match paragraph_break.take().filter(|at| *at > limit / 2) {
+                Some(at) => {
+                    let rest = current.split_off(at);
+                    chunks.push(current.trim_end().to_string());
+                    current = rest.trim_start_matches('\n').to_string();
+                }
+                None => {
+                    if open_fence.is_some() {
+                        current.push('\n');
+                        current.push_str(FENCE);
+                    }
+                    chunks.push(std::mem::take(&mut current));
+                    current = open_fence.clone().unwrap_or_default();
+                    break;
+                }
+            }
+        }
+
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        if line.trim().is_empty() && open_fence.is_none() {
+            paragraph_break = Some(current.len());
+        }
+        current.push_str(line);
+
+        if line.trim_start().starts_with(FENCE) {
+            open_fence = match open_fence {
+                Some(_) => None,
+                None if line.trim().len() <= MAX_FENCE_HEADER => Some(line.trim().to_string()),
+                None => Some(FENCE.to_string()),
+            };
+        }
+    }
+
+    if open_fence.is_some() {
+        current.push('\n');
+        current.push_str(FENCE);
+    }
+    chunks.push(current);
+
+    chunks.retain(|chunk| !chunk.trim().is_empty());
+    chunks
+}
+
+/// Replies to a command with text of any length: split across several messages, or attached
+/// as a file if it's very long. Returns the last message sent.
+pub async fn reply_long(ctx: Context<'_>, text: &str) -> Result<MessageId, Error> {
+    finish_reply(ctx, None, text, None).await
+}
+
+/// Sends the final version of text that `preview` (if any) has been showing while it was being
+/// written, with `footer` as a last line. Returns the last message sent.
+pub async fn finish_reply(
+    ctx: Context<'_>,
+    preview: Option<ReplyHandle<'_>>,
+    text: &str,
+    footer: Option<&str>,
+) -> Result<MessageId, Error> {
+    let (first, rest, file) = match plan_delivery(text, footer) {
+        Delivery::Messages(mut chunks) => (chunks.remove(0), chunks, None),
+        Delivery::File(note, file) => (note, Vec::new(), Some(file)),
+    };
+
+    let first = CreateReply::default().content(first).allowed_mentions(CreateAllowedMentions::new());
+    let mut last = match preview {
+        Some(preview) => {
+            preview.edit(ctx, first).await?;
+            preview.message().await?.id
+        }
+        None => ctx.send(first).await?.message().await?.id,
+    };
+
+    for chunk in rest {
+        let reply = ctx.send(CreateReply::default()
+            .content(chunk)
+            .allowed_mentions(CreateAllowedMentions::new()))
+            .await?;
+        last = reply.message().await?.id;
+    }
+    if let Some(file) = file {
+        last = ctx.send(CreateReply::default().attachment(file)).await?.message().await?.id;
+    }
+
+    Ok(last)
+}
+
+/// The channel message counterpart of `finish_reply`: replaces `preview` (or replies to
+/// `reference` if there's no preview yet) with the final text. Returns the last message sent.
+pub async fn finish_message(
+    http: &Http,
+    channel_id: ChannelId,
+    preview: Option<Message>,
+    reference: Option<&Message>,
+    text: &str,
+    footer: Option<&str>,
+) -> Result<MessageId, Error> {
+    let (first, rest, file) = match plan_delivery(text, footer) {
+        Delivery::Messages(mut chunks) => (chunks.remove(0), chunks, None),
+        Delivery::File(note, file) => (note, Vec::new(), Some(file)),
+    };
+
+    let mut last = match preview {
+        Some(mut preview) => {
+            preview.edit(http, EditMessage::new().content(first)).await?;
+            preview.id
+        }
+        None => {
+            let mut message = CreateMessage::new().content(first).allowed_mentions(CreateAllowedMentions::new());
+            if let Some(reference) = reference {
+                message = message.reference_message(reference);
+            }
+            channel_id.send_message(http, message).await?.id
+        }
+    };
+
+    for chunk in rest {
+        let message = CreateMessage::new().content(chunk).allowed_mentions(CreateAllowedMentions::new());
+        last = channel_id.send_message(http, message).await?.id;
+    }
+    if let Some(file) = file {
+        last = channel_id.send_message(http, CreateMessage::new().add_file(file)).await?.id;
+    }
+
+    Ok(last)
+}
+
+/// Posts a notice to the guild's moderation log channel, if it has one
+pub async fn alert_mod_log(http: &Http, data: &Data, guild_id: GuildId, message: &str) -> Result<(), Error> {
+    match data.database.fetch_warn_channel(guild_id.get() as i64).await? {
+        Some(channel_id) => {
+            ChannelId::new(channel_id as u64)
+                .send_message(http, CreateMessage::new()
+                    .content(message)
+                    .allowed_mentions(CreateAllowedMentions::new()))
+                .await?;
+        }
+        None => {
+            tracing::warn!("No moderation log channel set for guild {}", guild_id);
+        }
+    }
+    Ok(())
+}
+
+enum Delivery {
+    /// One or more messages, each within the length limit
+    Messages(Vec<String>),
+    /// A short note followed by the text as a file
+    File(String, CreateAttachment),
+}
+
+fn plan_delivery(text: &str, footer: Option<&str>) -> Delivery {
+    let footer_length = footer.map_or(0, |footer| footer.len() + 1);
+    let mut chunks = split_message(text, MAX_MESSAGE_LENGTH - footer_length);
+
+    if chunks.len() > MAX_CHUNKS {
+        let mut note = "The response was too long for a message, so here it is as a file.".to_string();
+        if let Some(footer) = footer {
+            note = format!("{}\n{}", note, footer);
+        }
+        return Delivery::File(note, CreateAttachment::bytes(text.as_bytes(), "response.md"));
+    }
+
+    if let (Some(footer), Some(last)) = (footer, chunks.last_mut()) {
+        last.push('\n');
+        last.push_str(footer);
+    }
+    Delivery::Messages(chunks)
+}
+
+/// Breaks a line longer than `max` bytes into pieces, at spaces where it can
+fn split_line(line: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.len() > max {
+        let mut end = max;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let cut = rest[..end].rfind(' ').filter(|at| *at > max / 2).map_or(end, |at| at + 1);
+        pieces.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    pieces.push(rest);
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_fence(line: &str) -> bool {
+        line.trim_start().starts_with(FENCE)
+    }
+
+    /// The text without fence lines or whitespace, which splitting may add or drop
+    fn content(text: &str) -> String {
+        text.lines()
+            .filter(|line| !is_fence(line))
+            .flat_map(|line| line.chars().filter(|c| !c.is_whitespace()))
+            .collect()
+    }
+
+    /// Splits `text`, checking each piece fits and closes the code blocks it opens
+    fn split_checked(text: &str, limit: usize) -> Vec<String> {
+        let chunks = split_message(text, limit);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert!(chunk.len() <= limit, "chunk {} is {} bytes, over {}", i, chunk.len(), limit);
+            let fences = chunk.lines().filter(|line| is_fence(line)).count();
+            assert!(fences % 2 == 0, "chunk {} has unbalanced fences:\n{}", i, chunk);
+        }
+        chunks
+    }
+
+    /// Like `split_checked`, also checking that nothing but fence lines and whitespace changed
+    fn check(text: &str, limit: usize) -> Vec<String> {
+        let chunks = split_checked(text, limit);
+        assert_eq!(content(&chunks.join("\n")), content(text));
+        chunks
+    }
+
+    fn paragraphs(count: usize, words: usize) -> String {
+        (0..count)
+            .map(|p| (0..words).map(|w| format!("word{}-{}", p, w)).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn code_block(info: &str, lines: usize, width: usize) -> String {
+        let body: Vec<String> = (0..lines).map(|i| format!("{:0>width$}", i, width = width)).collect();
+        format!("{}{}\n{}\n{}", FENCE, info, body.join("\n"), FENCE)
+    }
+
+    #[test]
+    fn leaves_short_text_alone() {
+        assert_eq!(split_message("hello\n\nworld", 2000), vec!["hello\n\nworld".to_string()]);
+        assert_eq!(split_message("  ", 2000), vec!["(empty)".to_string()]);
+    }
+
+    #[test]
+    fn splits_between_paragraphs() {
+        let text = paragraphs(40, 30);
+        let chunks = check(&text, 2000);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.ends_with(|c: char| c.is_ascii_digit()), "cut inside a paragraph: {:?}", chunk);
+        }
+    }
+
+    #[test]
+    fn reopens_code_blocks_with_their_language() {
+        let text = format!("{}\n\n{}\n\n{}", paragraphs(3, 20), code_block("rust", 300, 40), paragraphs(3, 20));
+        let chunks = check(&text, 2000);
+        assert!(chunks.len() > 2);
+        for chunk in &chunks[1..chunks.len() - 1] {
+            assert!(chunk.starts_with("```rust\n"), "not reopened: {:?}", &chunk[..20]);
+        }
+    }
+
+    #[test]
+    fn stays_within_the_limit_with_long_fence_lines() {
+        for info in [200, 1000, 1950, 1990, 2500] {
+            // A fence line too long for a piece is cut, so the end of its info string becomes a
+            // line of code. Everything else has to come through.
+            let text = code_block(&"x".repeat(info), 100, 60);
+            for limit in [2000, 500] {
+                let chunks = split_checked(&text, limit);
+                assert_eq!(content(&chunks.join("\n")).replace('x', ""), content(&text));
+            }
+        }
+    }
+
+    #[test]
+    fn stays_within_the_limit_with_long_lines() {
+        let long = "word ".repeat(1000);
+        let unbroken = "é".repeat(3000);
+        let text = format!("{}\n{}\n{}", long, code_block("", 3, 10).replace("0000000000", &unbroken), long);
+        check(&text, 2000);
+        check(&text, 1998);
+        check(&text, 300);
+    }
+
+    #[test]
+    fn stays_within_the_limit_for_mixed_text() {
+        let mut text = String::new();
+        for i in 0..30 {
+            text += &paragraphs(i % 4 + 1, i * 7 % 50 + 1);
+            text += "\n\n";
+            text += &code_block(if i % 3 == 0 { "py" } else { "" }, i * 11 % 60, i * 13 % 120 + 1);
+            text += "\n";
+        }
+        for limit in [2000, 1990, 1500, 700, 300] {
+            check(&text, limit);
+        }
+    }
+}
\ No newline at end of file
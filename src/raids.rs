@@ -0,0 +1,183 @@
+// raids.rs
+pub mod calendar;
+pub mod roster;
+
+use crate::config::RaidReminderConfig;
+use crate::database::{Database, DueRaidReminder, NewRaidEvent, RaidEvent, RaidRecurrence};
+use crate::error::Error;
+use crate::utils::next_weekly_occurrence;
+use chrono::{DateTime, Utc, Weekday};
+use chrono_tz::Tz;
+use poise::serenity_prelude::{ChannelId, CreateAllowedMentions, CreateMessage, EditChannel, Http, RoleId};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Discord's limit on channel topic length
+const MAX_TOPIC_LENGTH: usize = 1024;
+
+/// Builds a channel topic listing the upcoming raids, soonest first
+pub fn render_topic(events: &[RaidEvent]) -> String {
+    let Some((next, rest)) = events.split_first() else {
+        return "No raids scheduled".to_string();
+    };
+
+    let mut topic = format!("Next Meet Is: {} | Time: <t:{}:f>", next.raid, next.start_time.timestamp());
+    if next.mine {
+        topic += " M.I.N.E.";
+    }
+
+    for event in rest {
+        let mut entry = format!(" | Then: {} <t:{}:f>", event.raid, event.start_time.timestamp());
+        if event.mine {
+            entry += " M.I.N.E.";
+        }
+        if topic.len() + entry.len() > MAX_TOPIC_LENGTH {
+            break;
+        }
+        topic += &entry;
+    }
+
+    topic
+}
+
+/// Regenerates a channel's topic from the raids stored for it and returns the new topic
+pub async fn refresh_channel_topic(http: &Http, database: &Database, channel_id: ChannelId) -> Result<String, Error> {
+    let events = database.fetch_channel_raid_events(channel_id.get() as i64).await?;
+    let topic = render_topic(&events);
+
+    http.edit_channel(channel_id, &EditChannel::new().topic(&topic), None).await?;
+
+    Ok(topic)
+}
+
+/// Stores a new raid, updates the channel topic and posts a sign-up announcement in the
+/// channel, returning the raid's ID and the new topic
+pub async fn schedule(http: &Http, database: &Database, event: &NewRaidEvent<'_>) -> Result<(i64, String), Error> {
+    let id = database.create_raid_event(event).await?;
+    let channel_id = ChannelId::new(event.channel_id as u64);
+    let topic = refresh_channel_topic(http, database, channel_id).await?;
+
+    let announcement = RaidEvent {
+        id,
+        channel_id: event.channel_id,
+        raid: event.raid.to_string(),
+        start_time: event.start_time,
+        mine: event.mine,
+    };
+    channel_id.send_message(http, CreateMessage::new()
+        .embed(roster::embed(&announcement, &[], roster::party_size(database, event.guild_id, event.raid).await?))
+        .components(roster::buttons(id)))
+        .await?;
+
+    Ok((id, topic))
+}
+
+/// Schedules the next raid of a recurring schedule, returning its ID and start time
+pub async fn schedule_next_occurrence(
+    http: &Http,
+    database: &Database,
+    recurrence: &RaidRecurrence,
+) -> Result<(i64, DateTime<Utc>), Error> {
+    let tz: Tz = recurrence.timezone.parse()?;
+    let weekdays: Vec<Weekday> = recurrence.weekdays.iter()
+        .filter_map(|day| Weekday::try_from(*day as u8).ok())
+        .collect();
+    let start_time = next_weekly_occurrence(&weekdays, recurrence.local_time, tz, Utc::now())
+        .ok_or_else(|| Error::Unknown(format!("Recurring raid #{} has no upcoming dates", recurrence.id)))?;
+
+    let (id, _) = schedule(http, database, &NewRaidEvent {
+        guild_id: recurrence.guild_id,
+        channel_id: recurrence.channel_id,
+        raid: &recurrence.raid,
+        start_time,
+        mine: recurrence.mine,
+        created_by: recurrence.created_by,
+        recurrence_id: Some(recurrence.id),
+    }).await?;
+    database.store_raid_recurrence_last_occurrence(recurrence.id, start_time).await?;
+
+    Ok((id, start_time))
+}
+
+/// Background task that posts raid reminders and rolls recurring raids forward once their last
+/// session has ended. Everything it works from lives in Postgres, so anything that came due
+/// while the bot was offline is picked up on the first pass after a restart.
+pub async fn run_scheduler(http: Arc<Http>, database: Arc<Database>, config: RaidReminderConfig) {
+    let mut interval = tokio::time::interval(Duration::from_secs(config.poll_interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = send_due_reminders(&http, &database, &config).await {
+            tracing::error!("Failed to send raid reminders: {}", e);
+        }
+
+        if let Err(e) = roll_recurrences(&http, &database).await {
+            tracing::error!("Failed to roll recurring raids forward: {}", e);
+        }
+    }
+}
+
+async fn roll_recurrences(http: &Http, database: &Database) -> Result<(), Error> {
+    for recurrence in database.fetch_raid_recurrences_to_roll().await? {
+        match schedule_next_occurrence(http, database, &recurrence).await {
+            Ok((id, start_time)) => {
+                tracing::info!("Scheduled raid #{} at {} from recurring raid #{}", id, start_time, recurrence.id);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to schedule next raid for recurring raid #{}: {}", recurrence.id, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn send_due_reminders(http: &Http, database: &Database, config: &RaidReminderConfig) -> Result<(), Error> {
+    let due = database.fetch_due_raid_reminders(&config.offsets_minutes).await?;
+
+    // Rows come ordered by raid, then offset. When several offsets are due at once (a raid
+    // scheduled at short notice, or the bot was down) only the closest one is posted.
+    let mut remaining = due.as_slice();
+    while let Some(first) = remaining.first() {
+        let count = remaining.iter().take_while(|r| r.event.id == first.event.id).count();
+        let (group, rest) = remaining.split_at(count);
+        remaining = rest;
+
+        if let Err(e) = post_reminder(http, first).await {
+            tracing::warn!("Failed to post reminder for raid #{}: {}", first.event.id, e);
+        }
+
+        // Mark the whole group even if posting failed, so a deleted channel isn't retried forever
+        for reminder in group {
+            database.mark_raid_reminder_sent(reminder.event.id, reminder.offset_minutes).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn post_reminder(http: &Http, reminder: &DueRaidReminder) -> Result<(), Error> {
+    let event = &reminder.event;
+    let mut content = String::from("⏰ ");
+    let mut mentions = CreateAllowedMentions::new();
+    if let Some(role_id) = reminder.role_id {
+        content += &format!("<@&{}> ", role_id);
+        mentions = mentions.roles(vec![RoleId::new(role_id as u64)]);
+    }
+    content += &format!(
+        "**{}** starts <t:{}:R> (<t:{}:f>)",
+        event.raid,
+        event.start_time.timestamp(),
+        event.start_time.timestamp(),
+    );
+    if event.mine {
+        content += " M.I.N.E.";
+    }
+
+    ChannelId::new(event.channel_id as u64)
+        .send_message(http, CreateMessage::new().content(content).allowed_mentions(mentions))
+        .await?;
+
+    Ok(())
+}
\ No newline at end of file
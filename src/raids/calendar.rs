@@ -0,0 +1,157 @@
+// raids/calendar.rs
+use crate::config::CalendarConfig;
+use crate::database::{Database, RaidEvent};
+use crate::error::Error;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Longest request head the feed server will read before giving up on a connection
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+/// Longest a feed connection is kept open, so slow or idle clients can't hold on to one
+const CONNECTION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Builds an iCalendar file (RFC 5545) with one event per raid
+pub fn render_calendar(name: &str, events: &[RaidEvent], duration: Duration) -> String {
+    let now = Utc::now();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//rust_discord_bot//Raids//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    for event in events {
+        let mut summary = event.raid.clone();
+        if event.mine {
+            summary += " (M.I.N.E.)";
+        }
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:raid-{}@rust_discord_bot", event.id),
+            format!("DTSTAMP:{}", format_utc(now)),
+            format!("DTSTART:{}", format_utc(event.start_time)),
+            format!("DTEND:{}", format_utc(event.start_time + duration)),
+            format!("SUMMARY:{}", escape_text(&summary)),
+            format!("DESCRIPTION:{}", escape_text(&format!("Raid #{}. Sign up in the Discord channel.", event.id))),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+/// Serves each guild's upcoming raids as a calendar feed at `/raids/<feed token>.ics`, so
+/// calendar apps can subscribe to it and pick up changes. The token is a secret per guild,
+/// so knowing a guild's ID isn't enough to read its schedule.
+pub async fn run_feed_server(database: Arc<Database>, config: CalendarConfig) {
+    let listener = match TcpListener::bind(&config.feed_bind_address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to start calendar feed on {}: {}", config.feed_bind_address, e);
+            return;
+        }
+    };
+    tracing::info!("Serving raid calendar feeds on {}", config.feed_bind_address);
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                tracing::warn!("Failed to accept calendar feed connection: {}", e);
+                continue;
+            }
+        };
+
+        let database = database.clone();
+        let duration = Duration::minutes(config.event_duration_minutes);
+        tokio::spawn(async move {
+            match tokio::time::timeout(CONNECTION_TIMEOUT, serve_feed(stream, &database, duration)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::warn!("Failed to serve calendar feed: {}", e),
+                Err(_) => tracing::debug!("Closed a calendar feed connection that took too long"),
+            }
+        });
+    }
+}
+
+async fn serve_feed(mut stream: TcpStream, database: &Database, duration: Duration) -> Result<(), Error> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            return Ok(());
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+    let (method, path) = (request_line.next().unwrap_or_default(), request_line.next().unwrap_or_default());
+
+    let token = path.split('?').next()
+        .and_then(|path| path.strip_prefix("/raids/"))
+        .and_then(|file| file.strip_suffix(".ics"))
+        .filter(|token| !token.is_empty() && token.chars().all(|c| c.is_ascii_hexdigit()));
+    let guild_id = match token {
+        Some(token) => database.fetch_guild_by_calendar_feed_token(token).await?,
+        None => None,
+    };
+
+    let response = match (method, guild_id) {
+        ("GET" | "HEAD", Some(guild_id)) => {
+            let events = database.fetch_upcoming_raid_events(guild_id).await?;
+            let body = render_calendar("Raids", &events, duration);
+            http_response("200 OK", "text/calendar; charset=utf-8", &body, method == "HEAD")
+        }
+        ("GET" | "HEAD", None) => http_response("404 Not Found", "text/plain", "Not found", method == "HEAD"),
+        _ => http_response("405 Method Not Allowed", "text/plain", "Method not allowed", false),
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn http_response(status: &str, content_type: &str, body: &str, head_only: bool) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: max-age=300\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        if head_only { "" } else { body },
+    )
+}
+
+fn format_utc(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Splits a content line into 75-octet pieces joined by CRLF and a space, without breaking
+/// up multi-byte characters
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded += "\r\n";
+    folded
+}
\ No newline at end of file
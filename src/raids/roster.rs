@@ -0,0 +1,175 @@
+// raids/roster.rs
+use crate::database::{Database, RaidEvent, RaidSignup};
+use crate::error::Error;
+use crate::Data;
+use poise::serenity_prelude::{
+    ButtonStyle, ComponentInteraction, Context, CreateActionRow, CreateButton, CreateEmbed,
+    CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+
+/// Prefix of the custom ID on roster buttons, followed by `<raid id>:<role key>`
+pub const CUSTOM_ID_PREFIX: &str = "raid_signup:";
+/// Party size for raids that are no longer in the server's catalogue
+const DEFAULT_PARTY_SIZE: usize = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SignupRole {
+    Tank,
+    Healer,
+    Dps,
+    Tentative,
+    Decline,
+}
+
+impl SignupRole {
+    const ALL: [SignupRole; 5] = [
+        SignupRole::Tank,
+        SignupRole::Healer,
+        SignupRole::Dps,
+        SignupRole::Tentative,
+        SignupRole::Decline,
+    ];
+
+    fn key(&self) -> &'static str {
+        match self {
+            SignupRole::Tank => "tank",
+            SignupRole::Healer => "healer",
+            SignupRole::Dps => "dps",
+            SignupRole::Tentative => "tentative",
+            SignupRole::Decline => "decline",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|role| role.key() == key)
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            SignupRole::Tank => "Tank",
+            SignupRole::Healer => "Healer",
+            SignupRole::Dps => "DPS",
+            SignupRole::Tentative => "Tentative",
+            SignupRole::Decline => "Decline",
+        }
+    }
+
+    fn heading(&self) -> &'static str {
+        match self {
+            SignupRole::Tank => "🛡️ Tanks",
+            SignupRole::Healer => "💚 Healers",
+            SignupRole::Dps => "⚔️ DPS",
+            SignupRole::Tentative => "❔ Tentative",
+            SignupRole::Decline => "❌ Declined",
+        }
+    }
+
+    fn style(&self) -> ButtonStyle {
+        match self {
+            SignupRole::Tank | SignupRole::Healer | SignupRole::Dps => ButtonStyle::Primary,
+            SignupRole::Tentative => ButtonStyle::Secondary,
+            SignupRole::Decline => ButtonStyle::Danger,
+        }
+    }
+
+    /// Number of slots in a full party, `None` for statuses without a limit. A quarter of the
+    /// party tanks, a quarter heals and the rest are DPS, so 2/2/4 for 8 players and 1/1/2 for 4.
+    fn slots(&self, party_size: usize) -> Option<usize> {
+        match self {
+            SignupRole::Tank | SignupRole::Healer => Some(party_size / 4),
+            SignupRole::Dps => Some(party_size - 2 * (party_size / 4)),
+            SignupRole::Tentative | SignupRole::Decline => None,
+        }
+    }
+}
+
+/// The sign-up buttons attached to a raid announcement
+pub fn buttons(raid_event_id: i64) -> Vec<CreateActionRow> {
+    let buttons = SignupRole::ALL.iter().map(|role| {
+        CreateButton::new(format!("{}{}:{}", CUSTOM_ID_PREFIX, raid_event_id, role.key()))
+            .label(role.label())
+            .style(role.style())
+    }).collect();
+
+    vec![CreateActionRow::Buttons(buttons)]
+}
+
+/// Number of players the roster for a raid is laid out for, from the guild's raid catalogue
+pub async fn party_size(database: &Database, guild_id: i64, raid: &str) -> Result<usize, Error> {
+    Ok(database.fetch_raid_party_size(guild_id, raid).await?
+        .map_or(DEFAULT_PARTY_SIZE, |size| size.max(1) as usize))
+}
+
+pub fn embed(event: &RaidEvent, signups: &[RaidSignup], party_size: usize) -> CreateEmbed {
+    let mut embed = CreateEmbed::new()
+        .title(&event.raid)
+        .description(format!(
+            "<t:{}:F> (<t:{}:R>){}",
+            event.start_time.timestamp(),
+            event.start_time.timestamp(),
+            if event.mine { "\nM.I.N.E." } else { "" },
+        ))
+        .footer(CreateEmbedFooter::new(format!("Raid #{}", event.id)))
+        .color(0x5865F2);
+
+    for role in SignupRole::ALL {
+        let members: Vec<String> = signups.iter()
+            .filter(|s| s.role == role.key())
+            .map(|s| format!("<@{}>", s.user_id))
+            .collect();
+        let name = match role.slots(party_size) {
+            Some(slots) => format!("{} ({}/{})", role.heading(), members.len(), slots),
+            None => format!("{} ({})", role.heading(), members.len()),
+        };
+        let value = if members.is_empty() { "-".to_string() } else { members.join("\n") };
+        embed = embed.field(name, value, true);
+    }
+
+    embed
+}
+
+/// Handles a click on one of the roster buttons
+pub async fn handle_signup(ctx: &Context, interaction: &ComponentInteraction, data: &Data) -> Result<(), Error> {
+    let Some((raid_event_id, role)) = parse_custom_id(&interaction.data.custom_id) else {
+        return Ok(());
+    };
+    let guild_id = interaction.guild_id
+        .ok_or_else(|| Error::Unknown("Failed to get guild ID".to_string()))?;
+
+    let Some(event) = data.database.fetch_raid_event(guild_id.get() as i64, raid_event_id).await? else {
+        return respond_ephemeral(ctx, interaction, "This raid is no longer scheduled.").await;
+    };
+
+    let party_size = party_size(&data.database, guild_id.get() as i64, &event.raid).await?;
+    let user_id = interaction.user.id.get() as i64;
+    let signups = data.database.fetch_raid_signups(event.id).await?;
+    if let Some(slots) = role.slots(party_size) {
+        let taken = signups.iter()
+            .filter(|s| s.role == role.key() && s.user_id != user_id)
+            .count();
+        if taken >= slots {
+            return respond_ephemeral(ctx, interaction, &format!("All {} slots are taken.", role.label())).await;
+        }
+    }
+
+    data.database.store_raid_signup(event.id, user_id, role.key()).await?;
+    let signups = data.database.fetch_raid_signups(event.id).await?;
+
+    interaction.create_response(ctx, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new().embed(embed(&event, &signups, party_size)),
+    )).await?;
+
+    Ok(())
+}
+
+fn parse_custom_id(custom_id: &str) -> Option<(i64, SignupRole)> {
+    let (id, role) = custom_id.strip_prefix(CUSTOM_ID_PREFIX)?.split_once(':')?;
+    Some((id.parse().ok()?, SignupRole::from_key(role)?))
+}
+
+async fn respond_ephemeral(ctx: &Context, interaction: &ComponentInteraction, content: &str) -> Result<(), Error> {
+    interaction.create_response(ctx, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content(content).ephemeral(true),
+    )).await?;
+    Ok(())
+}
\ No newline at end of file
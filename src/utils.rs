@@ -1,46 +1,623 @@
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc, TimeZone};
+use chrono::{DateTime, Datelike, Duration, LocalResult, Month, NaiveDate, NaiveDateTime, NaiveTime, Offset, Utc, TimeZone, Weekday};
 use chrono_tz::Tz;
 use crate::error::Error;
 
-pub fn parse_datetime(month: &str, day: i64, year: i64, time: &str, timezone: &str) -> Result<DateTime<Utc>, Error> {
-    let month_num = match month.to_lowercase().as_str() {
-        "january" => 1, "february" => 2, "march" => 3, "april" => 4,
-        "may" => 5, "june" => 6, "july" => 7, "august" => 8,
-        "september" => 9, "october" => 10, "november" => 11, "december" => 12,
-        _ => return Err(Error::Unknown("Invalid month".to_string())),
+/// How to read a local time that a daylight saving change makes ambiguous or skips over
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DstResolution {
+    /// Use the first of two repeated times; move skipped times forward by the length of the gap
+    #[name = "Earlier time"] Earliest,
+    /// Use the second of two repeated times; move skipped times forward by the length of the gap
+    #[name = "Later time"] Latest,
+    /// Refuse both with `AmbiguousLocalTime` or `NonexistentLocalTime`
+    #[name = "Reject the time"] Reject,
+}
+
+/// Parses a time of day such as "8:30pm", "8 PM", "12:05 am", "20:00" or "noon". Hours are
+/// 1-12 with am/pm and 0-23 without, and minutes are always two digits.
+pub fn parse_time(time: &str) -> Result<NaiveTime, Error> {
+    let lowered = time.trim().to_lowercase();
+    let invalid = || Error::InvalidTime(time.trim().to_string());
+
+    match lowered.as_str() {
+        "noon" => return Ok(NaiveTime::from_hms_opt(12, 0, 0).unwrap_or_default()),
+        "midnight" => return Ok(NaiveTime::MIN),
+        _ => {}
+    }
+
+    let (clock, period) = match lowered.strip_suffix("am").or_else(|| lowered.strip_suffix("pm")) {
+        Some(clock) => (clock.trim_end(), Some(&lowered[clock.len()..])),
+        None => (lowered.as_str(), None),
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour, minute),
+        Some(_) => return Err(invalid()),
+        None if period.is_some() => (clock, "00"),
+        None => return Err(invalid()),
+    };
+    let is_number = |s: &str| !s.is_empty() && s.len() <= 2 && s.chars().all(|c| c.is_ascii_digit());
+    if !is_number(hour) || !is_number(minute) {
+        return Err(invalid());
+    }
+    let hour: u32 = hour.parse().map_err(|_| invalid())?;
+    let minute: u32 = minute.parse().map_err(|_| invalid())?;
+
+    let hour = match period {
+        Some(_) if !(1..=12).contains(&hour) => return Err(invalid()),
+        Some("am") => hour % 12,
+        Some(_) => hour % 12 + 12,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0).ok_or_else(invalid)
+}
+
+/// Parses a comma separated list of weekdays such as "Tue, Thu"
+pub fn parse_weekdays(weekdays: &str) -> Result<Vec<Weekday>, Error> {
+    let mut parsed: Vec<Weekday> = weekdays
+        .split(',')
+        .map(|day| day.trim().parse().map_err(|_| Error::Unknown(format!("Invalid weekday: {}", day.trim()))))
+        .collect::<Result<_, _>>()?;
+
+    parsed.sort_by_key(|day| day.num_days_from_monday());
+    parsed.dedup();
+    Ok(parsed)
+}
+
+/// Finds the first time after `after` that falls on one of `weekdays` at `time` local time in `tz`.
+/// Working in local time keeps a weekly raid at the same wall-clock time across DST changes.
+pub fn next_weekly_occurrence(weekdays: &[Weekday], time: NaiveTime, tz: Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let start = after.with_timezone(&tz).date_naive();
+
+    (0..=7)
+        .map(|offset| start + Duration::days(offset))
+        .filter(|date| weekdays.contains(&date.weekday()))
+        .filter_map(|date| resolve_local(tz, NaiveDateTime::new(date, time), DstResolution::Earliest).ok())
+        .map(|local| local.with_timezone(&Utc))
+        .find(|utc| *utc > after)
+}
+
+/// Turns a wall-clock time in `tz` into an instant, handling daylight saving changes as `dst` says
+pub fn resolve_local(tz: Tz, naive: NaiveDateTime, dst: DstResolution) -> Result<DateTime<Tz>, Error> {
+    match (tz.from_local_datetime(&naive), dst) {
+        (LocalResult::Single(dt), _) => Ok(dt),
+        (LocalResult::Ambiguous(earliest, _), DstResolution::Earliest) => Ok(earliest),
+        (LocalResult::Ambiguous(_, latest), DstResolution::Latest) => Ok(latest),
+        (LocalResult::Ambiguous(..), DstResolution::Reject) => {
+            Err(Error::AmbiguousLocalTime(naive, tz.name().to_string()))
+        }
+        (LocalResult::None, DstResolution::Reject) => {
+            Err(Error::NonexistentLocalTime(naive, tz.name().to_string()))
+        }
+        (LocalResult::None, _) => {
+            // Read the time with the offset from before the clocks jumped, which lands the same
+            // distance past the end of the gap as it was past the start
+            let before = tz.from_local_datetime(&(naive - Duration::days(1)))
+                .earliest()
+                .ok_or_else(|| Error::NonexistentLocalTime(naive, tz.name().to_string()))?;
+            let offset = Duration::seconds(before.offset().fix().local_minus_utc() as i64);
+            Ok(tz.from_utc_datetime(&(naive - offset)))
+        }
+    }
+}
+
+/// Parses a free-form date and time such as "next tuesday 8:30pm ET", "tomorrow 20:00",
+/// "oct 20 8pm", "2026-10-20T20:00:00-04:00" or "in 3h". Times without a zone are read in `default_tz`.
+/// Only times after `now` are accepted.
+pub fn parse_when(input: &str, default_tz: Tz, now: DateTime<Utc>, dst: DstResolution) -> Result<DateTime<Utc>, Error> {
+    let datetime = parse_datetime(input, default_tz, now, dst)?;
+    if datetime <= now {
+        return Err(Error::Unknown("That time has already passed".to_string()));
+    }
+    Ok(datetime)
+}
+
+fn parse_datetime(input: &str, default_tz: Tz, now: DateTime<Utc>, dst: DstResolution) -> Result<DateTime<Utc>, Error> {
+    let input = input.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(input) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    if let Some(relative) = input.to_lowercase().strip_prefix("in ") {
+        return now.checked_add_signed(parse_duration(relative)?).ok_or_else(too_far);
+    }
+
+    let mut words: Vec<&str> = input.split_whitespace().collect();
+    let tz = match words.last().and_then(|word| parse_timezone(word)) {
+        Some(tz) => {
+            words.pop();
+            tz
+        }
+        None => default_tz,
     };
 
-    let (hour, minute) = parse_time(time)?;
+    let mut time = None;
+    let mut date_words = Vec::new();
+    let mut date = None;
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i].trim_end_matches(',').to_lowercase();
+        let next = words.get(i + 1).map(|w| w.to_lowercase());
+
+        if let Some(local) = parse_local_iso(&word) {
+            date = Some(local.date());
+            time = Some(local.time());
+            i += 1;
+        } else if let Some((normalized, consumed)) = parse_time_words(&word, next.as_deref()) {
+            time = Some(parse_time(&normalized)?);
+            i += consumed;
+        } else {
+            if !matches!(word.as_str(), "at" | "on" | "the" | "@") {
+                date_words.push(word);
+            }
+            i += 1;
+        }
+    }
+
+    let time = time.ok_or_else(|| Error::Unknown("Please include a time, e.g. 8:30pm".to_string()))?;
+    let today = now.with_timezone(&tz).date_naive();
+    let resolve = |date: NaiveDate| {
+        resolve_local(tz, NaiveDateTime::new(date, time), dst).map(|local| local.with_timezone(&Utc))
+    };
+
+    if let Some(date) = date {
+        if !date_words.is_empty() {
+            return Err(Error::Unknown(format!("Couldn't understand \"{}\"", date_words.join(" "))));
+        }
+        return resolve(date);
+    }
 
-    let naive_date = NaiveDateTime::new(
-        NaiveDate::from_ymd_opt(year as i32, month_num, day as u32).ok_or_else(|| Error::Unknown("Invalid date".to_string()))?,
-        NaiveTime::from_hms_opt(hour, minute, 0).ok_or_else(|| Error::Unknown("Invalid time".to_string()))?,
-    );
+    // Dates that don't pin down a single day roll forward until they're in the future
+    let (first, later) = match parse_date_words(&date_words, today)? {
+        DateSpec::Exact(date) => return resolve(date),
+        DateSpec::NextDay(date) => (date, date.succ_opt()),
+        DateSpec::NextWeek(date) => (date, date.checked_add_signed(Duration::weeks(1))),
+        DateSpec::NextYear(date) => (date, date.with_year(date.year() + 1)),
+    };
 
-    let tz: Tz = timezone.parse()?;
-    Ok(tz.from_local_datetime(&naive_date)
-        .single() // This replaces `unwrap()` and handles ambiguous times
-        .ok_or_else(|| Error::Unknown("Ambiguous or non-existent local time".to_string()))?
-        .with_timezone(&Utc))
+    let datetime = resolve(first)?;
+    if datetime > now {
+        return Ok(datetime);
+    }
+    resolve(later.ok_or_else(|| Error::InvalidDate(first.to_string()))?)
 }
 
-fn parse_time(time: &str) -> Result<(u32, u32), Error> {
-    let parts: Vec<&str> = time.split(':').collect();
-    if parts.len() != 2 {
-        return Err(Error::Unknown("Invalid time format".to_string()));
+/// Parses a duration such as "3h", "90m", "1h30m" or "2 days"
+pub fn parse_duration(input: &str) -> Result<Duration, Error> {
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut unit = String::new();
+    let mut parts = 0;
+
+    let mut flush = |number: &mut String, unit: &mut String| -> Result<(), Error> {
+        if unit.as_str() == "and" && number.is_empty() {
+            unit.clear();
+            return Ok(());
+        }
+        let amount: i64 = number.parse()
+            .map_err(|_| Error::Unknown(format!("Invalid duration: {}", input)))?;
+        let part = match unit.as_str() {
+            "s" | "sec" | "secs" | "second" | "seconds" => Duration::try_seconds(amount),
+            "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes(amount),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Duration::try_hours(amount),
+            "d" | "day" | "days" => Duration::try_days(amount),
+            "w" | "wk" | "week" | "weeks" => Duration::try_weeks(amount),
+            _ => return Err(Error::Unknown(format!("Invalid duration unit: {}", unit))),
+        };
+        total = part.and_then(|part| total.checked_add(&part)).ok_or_else(too_far)?;
+        number.clear();
+        unit.clear();
+        parts += 1;
+        Ok(())
+    };
+
+    for c in input.trim().chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_digit() {
+            if !unit.is_empty() {
+                flush(&mut number, &mut unit)?;
+            }
+            number.push(c);
+        } else if c.is_alphabetic() {
+            unit.push(c.to_ascii_lowercase());
+        } else if c.is_whitespace() || c == ',' {
+            if !unit.is_empty() {
+                flush(&mut number, &mut unit)?;
+            }
+        } else {
+            return Err(Error::Unknown(format!("Invalid duration: {}", input)));
+        }
+    }
+
+    if parts == 0 || !number.is_empty() {
+        return Err(Error::Unknown(format!("Invalid duration: {}", input)));
     }
+    Ok(total)
+}
+
+/// For durations and times beyond what can be represented
+pub fn too_far() -> Error {
+    Error::Unknown("That's too far away".to_string())
+}
+
+/// Parses a timezone abbreviation (ET, PST, UTC, ...) or an IANA name such as "Europe/London"
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    let tz = match name.to_uppercase().as_str() {
+        "ET" | "EST" | "EDT" => Tz::America__New_York,
+        "CT" | "CST" | "CDT" => Tz::America__Chicago,
+        "MT" | "MST" | "MDT" => Tz::America__Denver,
+        "PT" | "PST" | "PDT" => Tz::America__Los_Angeles,
+        "AKT" | "AKST" | "AKDT" => Tz::America__Anchorage,
+        "HST" => Tz::Pacific__Honolulu,
+        "UTC" | "GMT" | "Z" => Tz::UTC,
+        _ => return name.parse().ok(),
+    };
+    Some(tz)
+}
+
+enum DateSpec {
+    /// Exactly this day
+    Exact(NaiveDate),
+    /// This day, or the next one if the time has already passed
+    NextDay(NaiveDate),
+    /// This day, or a week later if the time has already passed
+    NextWeek(NaiveDate),
+    /// This day, or a year later if the time has already passed
+    NextYear(NaiveDate),
+}
 
-    let hour: u32 = parts[0].parse().map_err(|_| Error::Unknown("Invalid hour".to_string()))?;
-    let minute: u32 = parts[1].split_whitespace().next().unwrap().parse().map_err(|_| Error::Unknown("Invalid minute".to_string()))?;
-    let period = time.to_lowercase();
+fn parse_date_words(words: &[String], today: NaiveDate) -> Result<DateSpec, Error> {
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let unknown = || Error::Unknown(format!("Couldn't understand the date \"{}\"", words.join(" ")));
 
-    let hour = if period.contains("pm") && hour != 12 {
-        hour + 12
-    } else if period.contains("am") && hour == 12 {
-        0
-    } else {
-        hour
+    let spec = match words.as_slice() {
+        [] => DateSpec::NextDay(today),
+        ["today"] | ["tonight"] => DateSpec::Exact(today),
+        ["tomorrow"] => DateSpec::Exact(today + Duration::days(1)),
+        [day] | ["this", day] if day.parse::<Weekday>().is_ok() => {
+            DateSpec::NextWeek(next_weekday(today, day.parse().map_err(|_| unknown())?))
+        }
+        ["next", day] => {
+            let weekday: Weekday = day.parse().map_err(|_| unknown())?;
+            DateSpec::Exact(next_weekday(today + Duration::days(1), weekday))
+        }
+        [date] if date.contains('-') => {
+            DateSpec::Exact(NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| unknown())?)
+        }
+        [date] if date.contains('/') => {
+            let parts: Vec<u32> = date.split('/')
+                .map(|part| part.parse().map_err(|_| unknown()))
+                .collect::<Result<_, _>>()?;
+            match parts.as_slice() {
+                [month, day] => DateSpec::NextYear(
+                    NaiveDate::from_ymd_opt(today.year(), *month, *day).ok_or_else(unknown)?,
+                ),
+                [month, day, year] => DateSpec::Exact(
+                    NaiveDate::from_ymd_opt(*year as i32, *month, *day).ok_or_else(unknown)?,
+                ),
+                _ => return Err(unknown()),
+            }
+        }
+        [first, second, rest @ ..] if rest.len() <= 1 => {
+            let (month, day) = match (month_from_name(first), month_from_name(second)) {
+                (Some(month), None) => (month, parse_day(second).ok_or_else(unknown)?),
+                (None, Some(month)) => (month, parse_day(first).ok_or_else(unknown)?),
+                (None, None) if parse_day(first).is_some() => return Err(Error::InvalidMonth(second.to_string())),
+                (None, None) if parse_day(second).is_some() => return Err(Error::InvalidMonth(first.to_string())),
+                _ => return Err(unknown()),
+            };
+            match rest {
+                [year] => DateSpec::Exact(
+                    NaiveDate::from_ymd_opt(year.parse().map_err(|_| unknown())?, month, day).ok_or_else(unknown)?,
+                ),
+                _ => DateSpec::NextYear(NaiveDate::from_ymd_opt(today.year(), month, day).ok_or_else(unknown)?),
+            }
+        }
+        _ => return Err(unknown()),
     };
 
-    Ok((hour, minute))
+    Ok(spec)
+}
+
+/// The first `weekday` on or after `from`
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_ahead = (7 + weekday.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from + Duration::days(days_ahead as i64)
+}
+
+fn month_from_name(name: &str) -> Option<u32> {
+    let name = name.trim_end_matches('.');
+    if name.len() < 3 {
+        return None;
+    }
+    (1..=12u8)
+        .filter_map(|number| Month::try_from(number).ok())
+        .find(|month| month.name().to_lowercase().starts_with(name) || name == "sept" && *month == Month::September)
+        .map(|month| month.number_from_month())
+}
+
+fn parse_day(day: &str) -> Option<u32> {
+    day.trim_end_matches(|c: char| c.is_alphabetic() || c == ',' || c == '.')
+        .parse()
+        .ok()
+}
+
+fn parse_local_iso(word: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%dt%H:%M", "%Y-%m-%dt%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(word, format).ok())
+}
+
+/// Recognises a time written as one or two words ("8pm", "8:30", "8:30 pm", "8 pm", "noon")
+/// and returns it as a single string for `parse_time`, along with the number of words used
+fn parse_time_words(word: &str, next: Option<&str>) -> Option<(String, usize)> {
+    match word {
+        "noon" => return Some(("12:00 pm".to_string(), 1)),
+        "midnight" => return Some(("12:00 am".to_string(), 1)),
+        _ => {}
+    }
+
+    let is_clock = |s: &str| {
+        let (hour, minute) = s.split_once(':').unwrap_or((s, "00"));
+        !hour.is_empty() && hour.len() <= 2 && hour.chars().all(|c| c.is_ascii_digit())
+            && minute.len() == 2 && minute.chars().all(|c| c.is_ascii_digit())
+    };
+    let with_minutes = |s: &str| if s.contains(':') { s.to_string() } else { format!("{}:00", s) };
+
+    if let Some(clock) = word.strip_suffix("am").or_else(|| word.strip_suffix("pm")) {
+        if is_clock(clock) {
+            return Some((format!("{} {}", with_minutes(clock), &word[clock.len()..]), 1));
+        }
+    }
+    if is_clock(word) {
+        if let Some(period @ ("am" | "pm")) = next {
+            return Some((format!("{} {}", with_minutes(word), period), 2));
+        }
+        if word.contains(':') {
+            return Some((word.to_string(), 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::TZ_VARIANTS;
+
+    /// A change of UTC offset: the instant it happens, and the offsets before and after in seconds
+    struct Transition {
+        at: NaiveDateTime,
+        before: i64,
+        after: i64,
+    }
+
+    fn offset_at(tz: Tz, utc: NaiveDateTime) -> i64 {
+        tz.offset_from_utc_datetime(&utc).fix().local_minus_utc() as i64
+    }
+
+    /// Every offset change in `tz` from 2020 to 2030, found by checking each day and then
+    /// narrowing down to the second
+    fn transitions(tz: Tz) -> Vec<Transition> {
+        let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let end = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        let mut found = Vec::new();
+        let mut day = start;
+        while day < end {
+            let next_day = day + Duration::days(1);
+            let (before, after) = (offset_at(tz, day), offset_at(tz, next_day));
+            if before != after {
+                let (mut low, mut high) = (day, next_day);
+                while high - low > Duration::seconds(1) {
+                    let middle = low + (high - low) / 2;
+                    if offset_at(tz, middle) == before {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+                found.push(Transition { at: high, before, after });
+            }
+            day = next_day;
+        }
+        found
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::new(date.parse().unwrap(), parse_time(time).unwrap())
+    }
+
+    #[test]
+    fn resolves_every_gap_and_overlap() {
+        let mut gaps = 0;
+        let mut overlaps = 0;
+
+        for tz in TZ_VARIANTS {
+            for transition in transitions(tz) {
+                let change = transition.after - transition.before;
+                // Halfway into the skipped or repeated stretch of wall-clock time
+                let naive = transition.at + Duration::seconds(transition.before.min(transition.after) + change.abs() / 2);
+                let resolve = |dst| resolve_local(tz, naive, dst);
+
+                if change > 0 {
+                    gaps += 1;
+                    let moved = tz.from_utc_datetime(&(naive - Duration::seconds(transition.before)));
+                    assert_eq!(moved.naive_local(), naive + Duration::seconds(change), "{} gap at {}", tz, naive);
+                    assert_eq!(resolve(DstResolution::Earliest).unwrap(), moved, "{} gap at {}", tz, naive);
+                    assert_eq!(resolve(DstResolution::Latest).unwrap(), moved, "{} gap at {}", tz, naive);
+                    assert!(
+                        matches!(resolve(DstResolution::Reject), Err(Error::NonexistentLocalTime(time, ref name)) if time == naive && name == tz.name()),
+                        "{} gap at {}", tz, naive,
+                    );
+                } else {
+                    overlaps += 1;
+                    let earliest = resolve(DstResolution::Earliest).unwrap();
+                    let latest = resolve(DstResolution::Latest).unwrap();
+                    assert_eq!(earliest.naive_utc(), naive - Duration::seconds(transition.before), "{} overlap at {}", tz, naive);
+                    assert_eq!(latest.naive_utc(), naive - Duration::seconds(transition.after), "{} overlap at {}", tz, naive);
+                    assert_eq!(earliest.naive_local(), naive);
+                    assert_eq!(latest.naive_local(), naive);
+                    assert!(
+                        matches!(resolve(DstResolution::Reject), Err(Error::AmbiguousLocalTime(time, ref name)) if time == naive && name == tz.name()),
+                        "{} overlap at {}", tz, naive,
+                    );
+                }
+            }
+        }
+
+        assert!(gaps > 100 && overlaps > 100, "only found {} gaps and {} overlaps", gaps, overlaps);
+    }
+
+    #[test]
+    fn resolves_unchanged_times_the_same_way_for_every_choice() {
+        let naive = at("2026-07-01", "20:00");
+        for tz in TZ_VARIANTS {
+            let expected = tz.from_local_datetime(&naive).single().unwrap();
+            for dst in [DstResolution::Earliest, DstResolution::Latest, DstResolution::Reject] {
+                assert_eq!(resolve_local(tz, naive, dst).unwrap(), expected, "{} {:?}", tz, dst);
+            }
+        }
+    }
+
+    #[test]
+    fn parses_am_and_pm() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+
+        assert_eq!(parse_time("8:30pm").unwrap(), time(20, 30));
+        assert_eq!(parse_time("8 PM").unwrap(), time(20, 0));
+        assert_eq!(parse_time("8:30 am").unwrap(), time(8, 30));
+        assert_eq!(parse_time("12am").unwrap(), time(0, 0));
+        assert_eq!(parse_time("12:05 am").unwrap(), time(0, 5));
+        assert_eq!(parse_time("12pm").unwrap(), time(12, 0));
+        assert_eq!(parse_time("12:59pm").unwrap(), time(12, 59));
+        assert_eq!(parse_time("1am").unwrap(), time(1, 0));
+        assert_eq!(parse_time("noon").unwrap(), time(12, 0));
+        assert_eq!(parse_time("Midnight").unwrap(), time(0, 0));
+        assert_eq!(parse_time("20:00").unwrap(), time(20, 0));
+        assert_eq!(parse_time("0:00").unwrap(), time(0, 0));
+        assert_eq!(parse_time("23:59").unwrap(), time(23, 59));
+    }
+
+    #[test]
+    fn rejects_out_of_range_times() {
+        for time in ["25:99", "24:00", "23:60", "8:5", "8:300", "13pm", "0am", "00:30am", "20", "123:00", "8:3o", "", "pm", "-1:00", "8.30pm"] {
+            assert!(
+                matches!(parse_time(time), Err(Error::InvalidTime(ref input)) if input == time.trim()),
+                "{:?} was accepted",
+                time,
+            );
+        }
+    }
+
+    #[test]
+    fn reads_am_and_pm_in_dates() {
+        let now = utc("2026-10-19T12:00:00Z");
+        let when = |input| parse_when(input, Tz::UTC, now, DstResolution::Earliest).unwrap();
+
+        assert_eq!(when("tomorrow 8:30pm"), utc("2026-10-20T20:30:00Z"));
+        assert_eq!(when("tomorrow 8:30 pm"), utc("2026-10-20T20:30:00Z"));
+        assert_eq!(when("tomorrow 8 pm"), utc("2026-10-20T20:00:00Z"));
+        assert_eq!(when("tomorrow 12am"), utc("2026-10-20T00:00:00Z"));
+        assert_eq!(when("tomorrow at noon"), utc("2026-10-20T12:00:00Z"));
+        assert_eq!(when("tomorrow 20:00"), utc("2026-10-20T20:00:00Z"));
+        assert_eq!(when("8am"), utc("2026-10-20T08:00:00Z"));
+        assert_eq!(when("8pm"), utc("2026-10-19T20:00:00Z"));
+        assert_eq!(when("next tuesday 8:30pm ET"), utc("2026-10-21T00:30:00Z"));
+        assert_eq!(when("oct 20 8pm"), utc("2026-10-20T20:00:00Z"));
+        assert_eq!(when("in 3h"), utc("2026-10-19T15:00:00Z"));
+    }
+
+    #[test]
+    fn resolves_dates_across_clock_changes() {
+        let now = utc("2026-01-01T00:00:00Z");
+        let when = |input, dst| parse_when(input, Tz::America__New_York, now, dst);
+
+        // Clocks in New York go forward from 2:00 to 3:00 on March 8, 2026
+        assert_eq!(when("2026-03-08 2:30am", DstResolution::Earliest).unwrap(), utc("2026-03-08T07:30:00Z"));
+        assert_eq!(when("2026-03-08 2:30am", DstResolution::Latest).unwrap(), utc("2026-03-08T07:30:00Z"));
+        assert!(matches!(
+            when("2026-03-08 2:30am", DstResolution::Reject),
+            Err(Error::NonexistentLocalTime(time, ref name)) if time == at("2026-03-08", "2:30am") && name == "America/New_York",
+        ));
+
+        // And back from 2:00 to 1:00 on November 1, 2026
+        assert_eq!(when("2026-11-01 1:30am", DstResolution::Earliest).unwrap(), utc("2026-11-01T05:30:00Z"));
+        assert_eq!(when("2026-11-01 1:30am", DstResolution::Latest).unwrap(), utc("2026-11-01T06:30:00Z"));
+        assert!(matches!(
+            when("2026-11-01 1:30am", DstResolution::Reject),
+            Err(Error::AmbiguousLocalTime(time, ref name)) if time == at("2026-11-01", "1:30am") && name == "America/New_York",
+        ));
+    }
+
+    #[test]
+    fn reports_each_kind_of_mistake() {
+        let now = utc("2024-03-01T12:00:00Z");
+        let when = |input| parse_when(input, Tz::UTC, now, DstResolution::Reject);
+
+        assert!(matches!(when("tomorrow 25:99"), Err(Error::InvalidTime(ref time)) if time == "25:99"));
+        assert!(matches!(when("tomorrow 13pm"), Err(Error::InvalidTime(ref time)) if time == "13:00 pm"));
+        assert!(matches!(when("14 smarch 8pm"), Err(Error::InvalidMonth(ref month)) if month == "smarch"));
+        assert!(matches!(when("smarch 14 8pm"), Err(Error::InvalidMonth(ref month)) if month == "smarch"));
+        // February 29 has passed this year and the next one isn't for four years
+        assert!(matches!(when("feb 29 8pm"), Err(Error::InvalidDate(ref date)) if date == "2024-02-29"));
+        assert!(matches!(
+            parse_when("2024-03-10 2:30am ET", Tz::UTC, now, DstResolution::Reject),
+            Err(Error::NonexistentLocalTime(..)),
+        ));
+        assert!(matches!(
+            parse_when("2024-11-03 1:30am ET", Tz::UTC, now, DstResolution::Reject),
+            Err(Error::AmbiguousLocalTime(..)),
+        ));
+        assert!(matches!(when("tomorrow"), Err(Error::Unknown(_))));
+        assert!(matches!(when("someday 8pm"), Err(Error::Unknown(_))));
+        assert!(matches!(when("feb 30 8pm"), Err(Error::Unknown(_))));
+        assert!(matches!(when("in 3 fortnights"), Err(Error::Unknown(_))));
+    }
+
+    #[test]
+    fn rejects_times_that_have_passed() {
+        let now = utc("2026-10-19T12:00:00Z");
+        let when = |input| parse_when(input, Tz::UTC, now, DstResolution::Earliest);
+        let passed = |result: Result<DateTime<Utc>, Error>| {
+            matches!(result, Err(Error::Unknown(ref message)) if message == "That time has already passed")
+        };
+
+        assert!(passed(when("2020-01-01 8pm")));
+        assert!(passed(when("today 8am")));
+        assert!(passed(when("today noon")));
+        assert!(passed(when("2026-10-19T11:59:00Z")));
+        assert!(passed(when("in 0m")));
+        assert_eq!(when("today 12:01").unwrap(), utc("2026-10-19T12:01:00Z"));
+    }
+
+    #[test]
+    fn rejects_durations_too_long_to_represent() {
+        let now = utc("2026-10-19T12:00:00Z");
+        fn too_far<T>(result: Result<T, Error>) -> bool {
+            matches!(result, Err(Error::Unknown(ref message)) if message == "That's too far away")
+        }
+
+        assert!(too_far(parse_when("in 100000000w", Tz::UTC, now, DstResolution::Earliest)));
+        assert!(too_far(parse_when("in 99999999999999w", Tz::UTC, now, DstResolution::Earliest)));
+        assert!(too_far(parse_duration("99999999999999w")));
+        assert!(too_far(parse_duration("9223372036854775807s")));
+        assert!(too_far(parse_duration("10000000000w 10000000000w")));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+    }
+
+    #[test]
+    fn finds_weekly_occurrences_at_the_same_local_time() {
+        let tz = Tz::America__New_York;
+        let time = parse_time("8pm").unwrap();
+        let next = |after| next_weekly_occurrence(&[Weekday::Sun], time, tz, utc(after)).unwrap();
+
+        // The Sundays either side of the clocks going forward on March 8, 2026
+        assert_eq!(next("2026-03-02T02:00:00Z"), utc("2026-03-09T00:00:00Z"));
+        assert_eq!(next("2026-03-09T00:00:00Z"), utc("2026-03-16T00:00:00Z"));
+        assert_eq!(next("2026-03-08T23:59:59Z"), utc("2026-03-09T00:00:00Z"));
+    }
 }
\ No newline at end of file